@@ -1,516 +1,4534 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{AccountInfo, next_account_info},
-    clock::Clock,
-    entrypoint,
+    clock::{Clock, DEFAULT_MS_PER_SLOT, DEFAULT_SLOTS_PER_EPOCH},
+    ed25519_program, entrypoint,
     entrypoint::ProgramResult,
+    hash::hashv,
+    instruction::{AccountMeta, Instruction},
+    log::sol_log_data,
     msg,
-    // program::invoke,
-    program::invoke_signed,
+    program::{invoke, invoke_signed},
     program_error::ProgramError,
     program_pack::Pack,
     pubkey::Pubkey,
-    system_instruction,
-    sysvar::{Sysvar, rent::Rent},
+    sysvar::{
+        Sysvar,
+        instructions::{
+            self as instructions_sysvar, load_current_index_checked, load_instruction_at_checked,
+        },
+        rent::Rent,
+        slot_hashes,
+    },
+};
+#[allow(deprecated)]
+use solana_program::{system_instruction, system_program};
+use spl_token::{
+    ID as TOKEN_PROGRAM_ID,
+    instruction::transfer,
+    state::{AccountState, Mint},
 };
-use spl_token::{ID as TOKEN_PROGRAM_ID, instruction::transfer, state::Mint};
+
+//most recipients a single Airdrop instruction accepts
+pub const MAX_AIRDROP_RECIPIENTS: usize = 20;
+
+//max claims a sliding window policy can track per user
+pub const MAX_WINDOW_CLAIMS: usize = 16;
+
+//tick value for "no claim yet"
+const NEVER: i64 = i64::MIN;
+
+//what cooldowns and rate limit windows are measured in
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CooldownUnit {
+    Seconds,
+    Slots,
+    Epochs,
+}
+
+impl CooldownUnit {
+    //current time in this unit
+    pub fn current_tick(&self, clock: &Clock) -> i64 {
+        match self {
+            CooldownUnit::Seconds => clock.unix_timestamp,
+            CooldownUnit::Slots => clock.slot as i64,
+            CooldownUnit::Epochs => clock.epoch as i64,
+        }
+    }
+
+    //nominal length of one tick, epochs and slots assume the default slot time
+    fn tick_millis(&self) -> i128 {
+        match self {
+            CooldownUnit::Seconds => 1_000,
+            CooldownUnit::Slots => DEFAULT_MS_PER_SLOT as i128,
+            CooldownUnit::Epochs => (DEFAULT_SLOTS_PER_EPOCH * DEFAULT_MS_PER_SLOT) as i128,
+        }
+    }
+
+    //the tick in unit `to` that lies as far back from now as `tick` does in this unit
+    //partial ticks round towards now, so converted history never limits less than before
+    pub fn convert_tick(&self, tick: i64, to: CooldownUnit, clock: &Clock) -> i64 {
+        if tick == NEVER || *self == to {
+            return tick;
+        }
+
+        let elapsed_millis =
+            self.current_tick(clock).saturating_sub(tick).max(0) as i128 * self.tick_millis();
+        let elapsed = (elapsed_millis / to.tick_millis()).min(i64::MAX as i128) as i64;
+        to.current_tick(clock).saturating_sub(elapsed)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CooldownUnit::Seconds => "seconds",
+            CooldownUnit::Slots => "slots",
+            CooldownUnit::Epochs => "epochs",
+        }
+    }
+}
 
 //use claimed records stored in PDA
-#[derive(BorshDeserialize, BorshSerialize, Debug)]
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone)]
 pub struct UserClaimedRecord {
     pub user: Pubkey,
     pub last_claim_time: i64,
     pub total_claims: u64,
+    pub allowance: u64, //token bucket: tokens the user can claim right now
+    pub allowance_updated_at: i64, //token bucket: last time the allowance was refilled
+    pub recent_claims: [i64; MAX_WINDOW_CLAIMS], //sliding window: ring of claim times
+    pub recent_claims_len: u8, //sliding window: valid entries in the ring
+    pub recent_claims_next: u8, //sliding window: slot the next claim goes in
+    pub last_claim_slot: u64,
+    pub last_claim_tick: i64, //last claim in the faucet's cooldown unit
+    pub rate_limit_unit: CooldownUnit, //unit the rate limit fields above are measured in
+    pub rent_payer: Pubkey,   //who funded the record, refunded on close
+    pub bonded_lamports: u64, //refundable bond held on this record
+    pub bond_unlocks_at: i64, //unix time the bond can be reclaimed
+    pub banned: bool,         //bond was slashed, no more claims
+    pub total_returned: u64,  //tokens this user gave back with ReturnTokens
+    pub streak: u32,          //consecutive streak periods with a claim
+    pub streak_period: i64,   //streak period of the last claim
+    pub referrer: Option<Pubkey>, //who referred this user on their first claim
+}
+
+impl UserClaimedRecord {
+    //bytes reserved for the record account so new fields fit without a realloc
+    pub const SPACE: usize = 512;
+
+    pub fn new(user: Pubkey) -> Self {
+        UserClaimedRecord {
+            user,
+            last_claim_time: 0, //no prev claim for the first timers
+            total_claims: 0,
+            allowance: 0, //refilled to the full burst on the first claim
+            allowance_updated_at: NEVER,
+            recent_claims: [0; MAX_WINDOW_CLAIMS],
+            recent_claims_len: 0,
+            recent_claims_next: 0,
+            last_claim_slot: 0,
+            last_claim_tick: NEVER,
+            rate_limit_unit: CooldownUnit::Seconds,
+            rent_payer: user,
+            bonded_lamports: 0,
+            bond_unlocks_at: 0,
+            banned: false,
+            total_returned: 0,
+            streak: 0,
+            streak_period: 0,
+            referrer: None,
+        }
+    }
+
+    //where rent goes when the record is closed
+    pub fn rent_receiver(&self) -> Pubkey {
+        if self.rent_payer == Pubkey::default() {
+            self.user
+        } else {
+            self.rent_payer
+        }
+    }
+
+    //moves rate limit history to another unit, used when the faucet switches cooldown units
+    pub fn convert_rate_limit(&mut self, unit: CooldownUnit, clock: &Clock) {
+        let from = self.rate_limit_unit;
+        self.allowance_updated_at = from.convert_tick(self.allowance_updated_at, unit, clock);
+        self.last_claim_tick = from.convert_tick(self.last_claim_tick, unit, clock);
+        for tick in &mut self.recent_claims[..self.recent_claims_len as usize] {
+            *tick = from.convert_tick(*tick, unit, clock);
+        }
+        self.rate_limit_unit = unit;
+    }
+
+    //claim times still inside the sliding window ending at current_time
+    fn claims_in_window(&self, current_time: i64, window: i64) -> impl Iterator<Item = i64> + '_ {
+        self.recent_claims[..self.recent_claims_len as usize]
+            .iter()
+            .copied()
+            .filter(move |&t| current_time.saturating_sub(t) < window)
+    }
+
+    //forgets the most recent claim still inside the window
+    fn forget_recent_claim(&mut self, current_time: i64, window: i64) {
+        let newest = self.recent_claims[..self.recent_claims_len as usize]
+            .iter_mut()
+            .filter(|t| current_time.saturating_sub(**t) < window)
+            .max_by_key(|t| **t);
+        if let Some(t) = newest {
+            *t = NEVER;
+        }
+    }
+
+    fn push_recent_claim(&mut self, current_time: i64) {
+        let next = self.recent_claims_next as usize % MAX_WINDOW_CLAIMS;
+        self.recent_claims[next] = current_time;
+        self.recent_claims_next = ((next + 1) % MAX_WINDOW_CLAIMS) as u8;
+        self.recent_claims_len = (self.recent_claims_len + 1).min(MAX_WINDOW_CLAIMS as u8);
+    }
+}
+
+//per user rate limit, chosen per faucet
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitPolicy {
+    //one claim every cooldown_seconds (from the faucet config, in its cooldown unit)
+    FixedCooldown,
+    //allowance refills by refill_amount every refill_interval, up to burst
+    TokenBucket {
+        refill_amount: u64,
+        refill_interval: i64,
+        burst: u64,
+    },
+    //at most max_claims within any window_seconds
+    SlidingWindow {
+        max_claims: u8,
+        window_seconds: i64,
+    },
+    //token bucket refilling tokens_per_claim every cooldown_seconds, up to tokens_per_claim
+    //both are read from the faucet config on each claim, so config updates apply right away
+    ConfigBucket,
+}
+
+impl RateLimitPolicy {
+    pub fn validate(&self) -> Result<(), FaucetError> {
+        match *self {
+            RateLimitPolicy::FixedCooldown | RateLimitPolicy::ConfigBucket => Ok(()),
+            RateLimitPolicy::TokenBucket {
+                refill_interval,
+                burst,
+                ..
+            } if refill_interval >= 0 && burst > 0 => Ok(()),
+            RateLimitPolicy::SlidingWindow {
+                max_claims,
+                window_seconds,
+            } if max_claims > 0
+                && max_claims as usize <= MAX_WINDOW_CLAIMS
+                && window_seconds >= 0 =>
+            {
+                Ok(())
+            }
+            _ => Err(FaucetError::InvalidConfig),
+        }
+    }
+
+    //the token bucket a ConfigBucket stands for, other policies are returned as they are
+    pub fn resolve(&self, cooldown_seconds: i64, tokens_per_claim: u64) -> RateLimitPolicy {
+        match *self {
+            RateLimitPolicy::ConfigBucket => RateLimitPolicy::TokenBucket {
+                refill_amount: tokens_per_claim,
+                refill_interval: cooldown_seconds,
+                burst: tokens_per_claim,
+            },
+            policy => policy,
+        }
+    }
+
+    //true when a fresh record would allow nothing more than this one does
+    pub fn is_idle(
+        &self,
+        cooldown_seconds: i64,
+        tokens_per_claim: u64,
+        record: &UserClaimedRecord,
+        current_time: i64,
+    ) -> bool {
+        match *self {
+            RateLimitPolicy::ConfigBucket => self
+                .resolve(cooldown_seconds, tokens_per_claim)
+                .is_idle(cooldown_seconds, tokens_per_claim, record, current_time),
+            RateLimitPolicy::FixedCooldown => {
+                current_time.saturating_sub(record.last_claim_tick) >= cooldown_seconds
+            }
+            RateLimitPolicy::TokenBucket { burst, .. } => {
+                let mut refilled = record.clone();
+                self.try_consume(
+                    cooldown_seconds,
+                    tokens_per_claim,
+                    &mut refilled,
+                    current_time,
+                    burst,
+                )
+                .is_ok()
+            }
+            RateLimitPolicy::SlidingWindow { window_seconds, .. } => record
+                .claims_in_window(current_time, window_seconds)
+                .next()
+                .is_none(),
+        }
+    }
+
+    //rewards returning amount tokens, a full tokens_per_claim undoes one claim
+    pub fn credit_return(
+        &self,
+        cooldown_seconds: i64,
+        tokens_per_claim: u64,
+        record: &mut UserClaimedRecord,
+        current_time: i64,
+        amount: u64,
+    ) {
+        if tokens_per_claim == 0 {
+            return;
+        }
+
+        match *self {
+            RateLimitPolicy::ConfigBucket => self
+                .resolve(cooldown_seconds, tokens_per_claim)
+                .credit_return(
+                    cooldown_seconds,
+                    tokens_per_claim,
+                    record,
+                    current_time,
+                    amount,
+                ),
+            RateLimitPolicy::FixedCooldown => {
+                if record.last_claim_tick == NEVER {
+                    return;
+                }
+                let credit = (cooldown_seconds.max(0) as u128 * amount as u128
+                    / tokens_per_claim as u128)
+                    .min(cooldown_seconds.max(0) as u128) as i64;
+                record.last_claim_tick = record.last_claim_tick.saturating_sub(credit);
+            }
+            RateLimitPolicy::TokenBucket { burst, .. } => {
+                //a record that never claimed already starts with the full burst
+                if record.allowance_updated_at != NEVER {
+                    record.allowance = record.allowance.saturating_add(amount).min(burst);
+                }
+            }
+            RateLimitPolicy::SlidingWindow { window_seconds, .. } => {
+                let claims = (amount / tokens_per_claim).min(MAX_WINDOW_CLAIMS as u64);
+                for _ in 0..claims {
+                    record.forget_recent_claim(current_time, window_seconds);
+                }
+            }
+        }
+    }
+
+    //the policy with token bucket amounts scaled by emitted / tokens_per_claim
+    //a ConfigBucket scales by being given the emitted amount, count based policies by claim size
+    pub fn decayed(&self, tokens_per_claim: u64, emitted: u64) -> RateLimitPolicy {
+        match *self {
+            RateLimitPolicy::TokenBucket {
+                refill_amount,
+                refill_interval,
+                burst,
+            } if tokens_per_claim > 0 => {
+                let scale = |amount: u64| {
+                    (amount as u128 * emitted as u128 / tokens_per_claim as u128)
+                        .min(u64::MAX as u128) as u64
+                };
+                RateLimitPolicy::TokenBucket {
+                    refill_amount: scale(refill_amount),
+                    refill_interval,
+                    burst: scale(burst),
+                }
+            }
+            policy => policy,
+        }
+    }
+
+    //largest single claim this policy can ever allow
+    pub fn max_claim(&self, tokens_per_claim: u64) -> u64 {
+        match *self {
+            RateLimitPolicy::TokenBucket { burst, .. } => burst,
+            RateLimitPolicy::ConfigBucket => tokens_per_claim,
+            _ => u64::MAX,
+        }
+    }
+
+    //spends a claim of amount at current_time from the user's record
+    //times are in the faucet's cooldown unit, as is the wait returned on rejection
+    //on rejection the record is left untouched
+    pub fn try_consume(
+        &self,
+        cooldown_seconds: i64,
+        tokens_per_claim: u64,
+        record: &mut UserClaimedRecord,
+        current_time: i64,
+        amount: u64,
+    ) -> Result<(), i64> {
+        match *self {
+            RateLimitPolicy::ConfigBucket => {
+                return self
+                    .resolve(cooldown_seconds, tokens_per_claim)
+                    .try_consume(
+                        cooldown_seconds,
+                        tokens_per_claim,
+                        record,
+                        current_time,
+                        amount,
+                    );
+            }
+            RateLimitPolicy::FixedCooldown => {
+                let time_since_last_claim = current_time.saturating_sub(record.last_claim_tick);
+                if time_since_last_claim < cooldown_seconds {
+                    return Err(cooldown_seconds - time_since_last_claim);
+                }
+            }
+            RateLimitPolicy::TokenBucket {
+                refill_amount,
+                refill_interval,
+                burst,
+            } => {
+                let elapsed = current_time
+                    .saturating_sub(record.allowance_updated_at)
+                    .max(0);
+
+                let accrued = if refill_interval <= 0 || record.allowance_updated_at == NEVER {
+                    burst
+                } else {
+                    let accrued = elapsed as u128 * refill_amount as u128 / refill_interval as u128;
+                    accrued.min(u64::MAX as u128) as u64
+                };
+                let allowance = record.allowance.saturating_add(accrued).min(burst);
+
+                if allowance < amount {
+                    let missing = amount - allowance;
+                    let wait = if refill_amount == 0 {
+                        i64::MAX
+                    } else {
+                        (missing as u128 * refill_interval as u128)
+                            .div_ceil(refill_amount as u128)
+                            .min(i64::MAX as u128) as i64
+                    };
+                    return Err(wait);
+                }
+
+                record.allowance = allowance - amount;
+                record.allowance_updated_at = current_time;
+            }
+            RateLimitPolicy::SlidingWindow {
+                max_claims,
+                window_seconds,
+            } => {
+                let in_window = record.claims_in_window(current_time, window_seconds);
+                let (count, oldest) = in_window.fold((0usize, i64::MAX), |(count, oldest), t| {
+                    (count + 1, oldest.min(t))
+                });
+
+                if count >= max_claims as usize {
+                    return Err(oldest.saturating_add(window_seconds) - current_time);
+                }
+
+                record.push_recent_claim(current_time);
+            }
+        }
+
+        record.last_claim_tick = current_time;
+        Ok(())
+    }
 }
 
+//operations that can be paused independently (bits of FaucetConfig::paused)
+pub const PAUSE_CLAIMS: u8 = 1 << 0;
+pub const PAUSE_DEPOSITS: u8 = 1 << 1;
+pub const PAUSE_WITHDRAWALS: u8 = 1 << 2;
+pub const PAUSE_CONFIG_UPDATES: u8 = 1 << 3;
+pub const PAUSE_ALL: u8 = PAUSE_CLAIMS | PAUSE_DEPOSITS | PAUSE_WITHDRAWALS | PAUSE_CONFIG_UPDATES;
+
 //faucet config
 #[derive(BorshDeserialize, BorshSerialize, Debug)]
 pub struct FaucetConfig {
     pub admin: Pubkey,
     pub token_mint: Pubkey, //which token this faucet distributes
+    pub treasury: Pubkey,   //token account every payout comes from, owned by the config PDA
     pub tokens_per_claim: u64,
     pub cooldown_seconds: i64,
-    pub is_active: bool,
+    pub paused: u8,           //PAUSE_* flags for operations that are currently stopped
+    pub total_deposited: u64, //sum of all donor deposits
+    pub total_donors: u64,    //unique wallets that have deposited
+    pub claim_mode: ClaimMode,
+    pub rate_limit: RateLimitPolicy,
+    pub cooldown_unit: CooldownUnit, //unit for cooldown_seconds and rate limit intervals
+    pub starts_at: Option<i64>,      //claims rejected before this unix time
+    pub ends_at: Option<i64>,        //claims rejected from this unix time on
+    pub created_at: i64,             //unix time the faucet was initialized
+    pub emission: EmissionCurve,
+    pub low_water_mark: Option<u64>, //warn when a claim leaves the treasury below this
+    pub pause_on_low_water: bool,    //also deactivate the faucet when that happens
+    pub total_airdropped: u64,
+    pub total_airdrop_recipients: u64,
+    pub voucher_authority: Option<Pubkey>, //signs off-chain claim vouchers, None disables them
+    pub anti_bot: bool,                    //claims must be top level and alone in their transaction
+    pub token_gate: Option<TokenGate>,     //only holders of this token may claim
+    pub max_recipient_token_balance: Option<u64>, //claimers holding more of the faucet token are refused
+    pub max_recipient_lamports: Option<u64>,      //claimers holding more SOL are refused
+    pub claim_fee_lamports: u64, //charged to the claimer into the fee vault, 0 disables it
+    pub treasurer: Option<Pubkey>, //may sweep the fee vault, None leaves it to the admin
+    pub claim_bond_lamports: u64, //refundable bond locked per claim, 0 disables it
+    pub bond_holding_seconds: i64, //how long after the last claim the bond stays locked
+    pub streak: Option<StreakPolicy>, //loyalty multipliers for consecutive period claims
+    pub referral_bonus: u64, //credited to the referrer when a referred user first claims, 0 disables it
+    pub vesting: Option<VestingSchedule>, //claims vest in an escrow instead of paying out at once
+    pub basket: Vec<BasketEntry>, //extra mints every claim also dispenses
+    pub lottery: Option<Lottery>, //draws each claim's amount from weighted tiers
 }
 
-//instructions program will accept
-#[derive(BorshDeserialize, BorshSerialize, Debug)]
-pub enum FaucetInstruction {
-    //initialize faucet
-    //accounts :
-    //signer -> admin account
-    //writable -> faucet config account
-    //token mint account
-    InitializeFaucet {
+impl FaucetConfig {
+    //bytes reserved for the config account so new settings fit without a realloc
+    //(unused bytes decode as zero/None/first variant)
+    pub const SPACE: usize = 1024;
+
+    //fresh config with every optional feature off
+    pub fn new(
+        admin: Pubkey,
+        token_mint: Pubkey,
+        treasury: Pubkey,
         tokens_per_claim: u64,
         cooldown_seconds: i64,
-    },
-    //claims tokens from faucet
-    //accounts :
-    //signer -> user req tokens
-    //writable -> user claim record PDA
-    //writable -> user token account
-    //writable -> faucet treasury token account
-    //faucet config account
-    //token program
-    ClaimTokens,
-    UpdateFaucetConfig {
-        new_tokens_per_claim: Option<u64>,
-        new_cooldown_seconds: Option<i64>,
-        new_is_active: Option<bool>,
-    },
-    EmergencyPause,
-    WithdrawTreasury {
-        amount: u64,
-    },
+        created_at: i64,
+    ) -> Self {
+        FaucetConfig {
+            admin,
+            token_mint,
+            treasury,
+            tokens_per_claim,
+            cooldown_seconds,
+            paused: 0,
+            total_deposited: 0,
+            total_donors: 0,
+            claim_mode: ClaimMode::Fixed,
+            //one full claim per cooldown, users may split it into smaller claims
+            rate_limit: RateLimitPolicy::ConfigBucket,
+            cooldown_unit: CooldownUnit::Seconds,
+            starts_at: None,
+            ends_at: None,
+            created_at,
+            emission: EmissionCurve::Constant,
+            low_water_mark: None,
+            pause_on_low_water: false,
+            total_airdropped: 0,
+            total_airdrop_recipients: 0,
+            voucher_authority: None,
+            anti_bot: false,
+            token_gate: None,
+            max_recipient_token_balance: None,
+            max_recipient_lamports: None,
+            claim_fee_lamports: 0,
+            treasurer: None,
+            claim_bond_lamports: 0,
+            bond_holding_seconds: 0,
+            streak: None,
+            referral_bonus: 0,
+            vesting: None,
+            basket: Vec::new(),
+            lottery: None,
+        }
+    }
+
+    pub fn is_paused(&self, operation: u8) -> bool {
+        self.paused & operation != 0
+    }
+
+    pub fn has_ended(&self, current_time: i64) -> bool {
+        self.ends_at.is_some_and(|ends_at| current_time >= ends_at)
+    }
+
+    pub fn treasurer(&self) -> Pubkey {
+        self.treasurer.unwrap_or(self.admin)
+    }
+
+    //the config PDA signs for any token account it owns, so payouts must name the pinned one
+    pub fn check_treasury(&self, treasury_account: &AccountInfo) -> ProgramResult {
+        if *treasury_account.key != self.treasury {
+            msg!("Faucet treasury must be {}", self.treasury);
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(())
+    }
 }
 
-#[derive(Debug)]
-pub enum FaucetError {
-    CooldownNotMet,
-    FaucetInactive,
-    InsufficientFunds,
-    UnauthorizedAdmin,
+//config layout of the first release, before the account was padded to SPACE
+#[derive(BorshDeserialize, BorshSerialize, Debug)]
+pub struct LegacyFaucetConfig {
+    pub admin: Pubkey,
+    pub token_mint: Pubkey,
+    pub tokens_per_claim: u64,
+    pub cooldown_seconds: i64,
+    pub is_active: bool,
 }
 
-impl From<FaucetError> for ProgramError {
-    fn from(e: FaucetError) -> Self {
-        match e {
-            FaucetError::CooldownNotMet => ProgramError::Custom(1000),
-            FaucetError::FaucetInactive => ProgramError::Custom(1001),
-            FaucetError::InsufficientFunds => ProgramError::Custom(1002),
-            FaucetError::UnauthorizedAdmin => ProgramError::Custom(1003),
+impl LegacyFaucetConfig {
+    pub const LEN: usize = 32 + 32 + 8 + 8 + 1;
+
+    //same settings in the current layout, an inactive faucet comes back with claims paused
+    pub fn migrate(&self, treasury: Pubkey, created_at: i64) -> FaucetConfig {
+        let mut config = FaucetConfig::new(
+            self.admin,
+            self.token_mint,
+            treasury,
+            self.tokens_per_claim,
+            self.cooldown_seconds,
+            created_at,
+        );
+        if !self.is_active {
+            config.paused = PAUSE_CLAIMS;
         }
+        config
     }
 }
 
-entrypoint!(process_instruction);
+//claim record layout of the first release
+#[derive(BorshDeserialize, BorshSerialize, Debug)]
+pub struct LegacyClaimRecord {
+    pub user: Pubkey,
+    pub last_claim_time: i64,
+    pub total_claims: u64,
+}
 
-pub fn process_instruction(
-    program_id: &Pubkey,
-    accounts: &[AccountInfo],
-    instruction_data: &[u8],
-) -> ProgramResult {
-    let instruction = FaucetInstruction::try_from_slice(instruction_data)?;
+impl LegacyClaimRecord {
+    pub const LEN: usize = 32 + 8 + 8;
 
-    match instruction {
-        FaucetInstruction::InitializeFaucet {
-            tokens_per_claim,
-            cooldown_seconds,
-        } => {
-            msg!(
-                "Initializing faucet with {} tokens per claim, {} second cooldown",
-                tokens_per_claim,
-                cooldown_seconds
-            );
+    //current record that limits the user as if they claimed once at last_claim_time
+    pub fn migrate(&self) -> UserClaimedRecord {
+        let mut record = UserClaimedRecord::new(self.user);
+        record.last_claim_time = self.last_claim_time;
+        record.total_claims = self.total_claims;
+        if self.total_claims > 0 {
+            record.last_claim_tick = self.last_claim_time;
+            record.allowance_updated_at = self.last_claim_time;
+            record.push_recent_claim(self.last_claim_time);
+        }
+        record
+    }
+}
 
-            let accounts_iter = &mut accounts.iter();
+//how tokens_per_claim shrinks as the faucet ages or drains
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmissionCurve {
+    //always tokens_per_claim
+    Constant,
+    //halves every interval_seconds
+    StepHalving { interval_seconds: i64 },
+    //falls linearly to floor over duration_seconds, then stays there
+    LinearDecay { duration_seconds: i64, floor: u64 },
+    //full tokens_per_claim while the treasury holds full_at or more, scaled down below that
+    TreasuryProportional { full_at: u64 },
+}
 
-            //signer (admin account)
-            let admin_account = next_account_info(accounts_iter)?;
-            if !admin_account.is_signer {
-                msg!("Admin account must be a signer");
-                return Err(ProgramError::MissingRequiredSignature);
+impl EmissionCurve {
+    pub fn validate(&self) -> Result<(), FaucetError> {
+        match *self {
+            EmissionCurve::StepHalving { interval_seconds } if interval_seconds <= 0 => {
+                Err(FaucetError::InvalidConfig)
             }
+            EmissionCurve::LinearDecay {
+                duration_seconds, ..
+            } if duration_seconds <= 0 => Err(FaucetError::InvalidConfig),
+            EmissionCurve::TreasuryProportional { full_at: 0 } => Err(FaucetError::InvalidConfig),
+            _ => Ok(()),
+        }
+    }
 
-            //faucet config account (PDA creator)
-            let faucet_config_account = next_account_info(accounts_iter)?;
-
-            //token mint account (valid SOL token mint)
-            let token_mint_account = next_account_info(accounts_iter)?;
+    //tokens per claim for a faucet age_seconds old with treasury_balance left
+    pub fn evaluate(&self, tokens_per_claim: u64, age_seconds: i64, treasury_balance: u64) -> u64 {
+        let age_seconds = age_seconds.max(0);
 
-            //system program (required to create accounts)
-            let system_program = next_account_info(accounts_iter)?;
+        match *self {
+            EmissionCurve::Constant => tokens_per_claim,
+            EmissionCurve::StepHalving { interval_seconds } => {
+                let halvings = age_seconds / interval_seconds.max(1);
+                if halvings >= 64 {
+                    0
+                } else {
+                    tokens_per_claim >> halvings
+                }
+            }
+            EmissionCurve::LinearDecay {
+                duration_seconds,
+                floor,
+            } => {
+                let floor = floor.min(tokens_per_claim);
+                let duration_seconds = duration_seconds.max(1);
+                if age_seconds >= duration_seconds {
+                    return floor;
+                }
 
-            //validate that token mint is actually a mint account (account that stores global metadata about a token)
-            let mint_data = Mint::unpack(&token_mint_account.data.borrow())
-                .map_err(|_| ProgramError::InvalidAccountData)?;
-            msg!("Token mint validated. Supply {}", mint_data.supply);
+                let decayable = (tokens_per_claim - floor) as u128;
+                let remaining = (duration_seconds - age_seconds) as u128;
+                floor + (decayable * remaining / duration_seconds as u128) as u64
+            }
+            EmissionCurve::TreasuryProportional { full_at } => {
+                if treasury_balance >= full_at {
+                    tokens_per_claim
+                } else {
+                    (tokens_per_claim as u128 * treasury_balance as u128 / full_at as u128) as u64
+                }
+            }
+        }
+    }
+}
 
-            //create PDA for faucet config
-            let faucet_config_seed = b"faucet_config";
-            let (faucet_config_pda, bump_seed) =
-                Pubkey::find_program_address(&[faucet_config_seed], program_id);
+//how much a claim sends
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClaimMode {
+    //always send tokens_per_claim
+    Fixed,
+    //send whatever brings the user's balance up to target_balance
+    TopUpToTarget { target_balance: u64 },
+}
 
-            // Verify the passed account is the correct PDA
-            if faucet_config_pda != *faucet_config_account.key {
-                msg!("Faucet config account is not the correct PDA");
-                return Err(ProgramError::InvalidAccountData);
+impl ClaimMode {
+    pub fn claim_amount(&self, tokens_per_claim: u64, current_balance: u64) -> u64 {
+        match self {
+            ClaimMode::Fixed => tokens_per_claim,
+            ClaimMode::TopUpToTarget { target_balance } => {
+                target_balance.saturating_sub(current_balance)
             }
+        }
+    }
+}
 
-            //calculate required space for FaucetConfig
-            let config_data = FaucetConfig {
-                admin: *admin_account.key,
-                token_mint: *token_mint_account.key,
-                tokens_per_claim,
-                cooldown_seconds,
-                is_active: true,
-            };
+//most entries in a streak multiplier table
+pub const MAX_STREAK_TIERS: usize = 16;
+//multipliers are in basis points, this is 1x
+pub const BPS_DENOMINATOR: u64 = 10_000;
 
-            let required_space = borsh::to_vec(&config_data)?.len();
+//multiplies claims for users claiming in consecutive periods
+//the rate limit is only charged for the base claim, the boost is paid on top up to max_tokens_per_claim
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, PartialEq, Eq)]
+pub struct StreakPolicy {
+    pub period_seconds: i64,
+    pub multipliers_bps: Vec<u16>, //entry n applies to a streak of n + 1 periods, the last one beyond that
+    pub max_tokens_per_claim: u64, //cap on the multiplied amount
+}
 
-            //lamports for rent exemption
-            let rent = Rent::get()?;
-            let required_lamports = rent.minimum_balance(required_space);
+impl StreakPolicy {
+    pub fn validate(&self) -> Result<(), FaucetError> {
+        if self.period_seconds <= 0
+            || self.multipliers_bps.is_empty()
+            || self.multipliers_bps.len() > MAX_STREAK_TIERS
+            || self.max_tokens_per_claim == 0
+        {
+            return Err(FaucetError::InvalidConfig);
+        }
+        Ok(())
+    }
 
-            //creating the faucet config
-            let create_account_instruction = system_instruction::create_account(
-                admin_account.key,         //payer
-                faucet_config_account.key, //new account
-                required_lamports,         //lamports to transfer
-                required_space as u64,     //allocate the space
-                program_id,                //owner of new account
-            );
+    //extends the user's streak with a claim at current_time, a missed period starts over
+    pub fn record_claim(&self, record: &mut UserClaimedRecord, current_time: i64) {
+        let period = current_time.div_euclid(self.period_seconds);
+        if record.streak == 0 || period > record.streak_period + 1 {
+            record.streak = 1;
+        } else if period == record.streak_period + 1 {
+            record.streak = record.streak.saturating_add(1);
+        }
+        record.streak_period = period;
+    }
 
-            //invoke system program to create the new account (CPI)
-            invoke_signed(
-                &create_account_instruction,
-                &[
-                    admin_account.clone(),
-                    faucet_config_account.clone(),
-                    system_program.clone(),
-                ],
-                &[&[faucet_config_seed, &[bump_seed]]], //PDA sign
-            )?;
+    pub fn apply(&self, tokens_per_claim: u64, streak: u32) -> u64 {
+        let tier = (streak.max(1) as usize - 1).min(self.multipliers_bps.len() - 1);
+        let boosted =
+            tokens_per_claim as u128 * self.multipliers_bps[tier] as u128 / BPS_DENOMINATOR as u128;
+        (boosted.min(u64::MAX as u128) as u64).min(self.max_tokens_per_claim)
+    }
+}
 
-            //serialize and store the config of faucet
-            config_data.serialize(&mut &mut faucet_config_account.data.borrow_mut()[..])?;
+//claimers must hold at least min_balance of mint (1 for an NFT pass)
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenGate {
+    pub mint: Pubkey,
+    pub min_balance: u64,
+}
 
-            msg!("Faucet initialized successfully!");
-            msg!("Admin: {}", admin_account.key);
-            msg!("Token Mint: {}", token_mint_account.key);
-            msg!("PDA of Faucet: {}", faucet_config_pda);
-        }
+//pause/resume entries kept on the pause log PDA
+pub const PAUSE_LOG_LEN: usize = 8;
+pub const PAUSE_MEMO_LEN: usize = 32;
+
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PauseAction {
+    Pause,
+    Resume,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, Copy)]
+pub struct PauseLogEntry {
+    pub action: PauseAction,
+    pub flags: u8, //PAUSE_* operations affected
+    pub reason: u16,
+    pub memo: [u8; PAUSE_MEMO_LEN], //utf8, zero padded
+    pub admin: Pubkey,
+    pub timestamp: i64,
+}
+
+//ring buffer of the last PAUSE_LOG_LEN pause/resume events
+#[derive(BorshDeserialize, BorshSerialize, Debug)]
+pub struct PauseLog {
+    pub entries: [PauseLogEntry; PAUSE_LOG_LEN],
+    pub len: u8,  //valid entries
+    pub next: u8, //slot the next entry goes in
+}
+
+impl PauseLog {
+    pub fn push(&mut self, entry: PauseLogEntry) {
+        let next = self.next as usize % PAUSE_LOG_LEN;
+        self.entries[next] = entry;
+        self.next = ((next + 1) % PAUSE_LOG_LEN) as u8;
+        self.len = (self.len + 1).min(PAUSE_LOG_LEN as u8);
+    }
+
+    //entries from oldest to newest
+    pub fn history(&self) -> Vec<PauseLogEntry> {
+        let start = (self.next as usize + PAUSE_LOG_LEN - self.len as usize) % PAUSE_LOG_LEN;
+        (0..self.len as usize)
+            .map(|i| self.entries[(start + i) % PAUSE_LOG_LEN])
+            .collect()
+    }
+}
+
+//per donor contribution stored in PDA
+#[derive(BorshDeserialize, BorshSerialize, Debug)]
+pub struct DonorContribution {
+    pub donor: Pubkey,
+    pub total_deposited: u64,
+    pub deposit_count: u64,
+    pub last_deposit_time: i64,
+}
+
+//most tiers in a lottery distribution
+pub const MAX_LOTTERY_TIERS: usize = 8;
+
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LotteryTier {
+    pub weight: u16, //relative odds against the other tiers
+    pub amount: u64,
+}
+
+//weighted claim amounts, e.g. 90 small : 10 jackpot
+//needs anti_bot, so a contract can't claim through CPI and revert losing draws,
+//and every tier must fit in a single claim under the rate limit
+//each draw costs a full claim whatever it pays, and prizes decay with the emission curve
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, PartialEq, Eq)]
+pub struct Lottery {
+    pub tiers: Vec<LotteryTier>,
+}
+
+impl Lottery {
+    pub fn validate(&self) -> Result<(), FaucetError> {
+        if self.tiers.is_empty()
+            || self.tiers.len() > MAX_LOTTERY_TIERS
+            || self
+                .tiers
+                .iter()
+                .any(|tier| tier.weight == 0 || tier.amount == 0)
+        {
+            return Err(FaucetError::InvalidConfig);
+        }
+        Ok(())
+    }
+
+    //whether even the largest prize can be paid out in one claim
+    pub fn fits(&self, max_claim: u64) -> bool {
+        self.tiers.iter().all(|tier| tier.amount <= max_claim)
+    }
+
+    //mixes a recent slot hash with the user and their claim count
+    //good enough for community events, a determined user can predict it
+    pub fn seed(slot_hash: &[u8], user: &Pubkey, total_claims: u64) -> u64 {
+        let hash = hashv(&[slot_hash, user.as_ref(), &total_claims.to_le_bytes()]);
+        u64::from_le_bytes(hash.to_bytes()[..8].try_into().unwrap())
+    }
+
+    //tier index and amount picked by seed
+    pub fn draw(&self, seed: u64) -> (u8, u64) {
+        let total_weight: u64 = self.tiers.iter().map(|tier| tier.weight as u64).sum();
+        let mut roll = seed % total_weight;
+        for (index, tier) in self.tiers.iter().enumerate() {
+            if roll < tier.weight as u64 {
+                return (index as u8, tier.amount);
+            }
+            roll -= tier.weight as u64;
+        }
+        unreachable!("roll is below the total weight")
+    }
+}
+
+//most extra mints a basket faucet dispenses per claim
+pub const MAX_BASKET_ENTRIES: usize = 4;
+
+//one extra mint dispensed with every claim, from a treasury owned by the config PDA
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BasketEntry {
+    pub mint: Pubkey,
+    pub treasury: Pubkey,
+    pub amount: u64,
+}
+
+//how claimed tokens unlock when the faucet vests claims
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VestingSchedule {
+    pub cliff_seconds: i64,    //nothing unlocks before this
+    pub duration_seconds: i64, //everything is unlocked after this, linearly in between
+}
+
+impl VestingSchedule {
+    pub fn validate(&self) -> Result<(), FaucetError> {
+        if self.duration_seconds <= 0
+            || self.cliff_seconds < 0
+            || self.cliff_seconds > self.duration_seconds
+        {
+            return Err(FaucetError::InvalidConfig);
+        }
+        Ok(())
+    }
+}
+
+//one vesting claim, on the schedule that was in force when it was made
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VestingGrant {
+    pub amount: u64,
+    pub start: i64,
+    pub schedule: VestingSchedule, //copied at claim time so config changes don't move it
+}
+
+impl VestingGrant {
+    pub fn vested(&self, current_time: i64) -> u64 {
+        let elapsed = current_time.saturating_sub(self.start);
+        if elapsed < self.schedule.cliff_seconds {
+            0
+        } else if elapsed >= self.schedule.duration_seconds {
+            self.amount
+        } else {
+            (self.amount as u128 * elapsed as u128 / self.schedule.duration_seconds as u128) as u64
+        }
+    }
+}
+
+//most grants vesting at once per user, fully vested ones free their slot
+pub const MAX_VESTING_GRANTS: usize = 8;
+
+//a user's vesting claims, tokens sit in the matching escrow token account
+#[derive(BorshDeserialize, BorshSerialize, Debug)]
+pub struct VestingAccount {
+    pub user: Pubkey,
+    pub total: u64,                //every token granted so far
+    pub released: u64,             //every token paid out so far
+    pub grants: Vec<VestingGrant>, //grants that were still vesting at the last claim, oldest first
+}
+
+impl VestingAccount {
+    pub const SPACE: usize = 32 + 8 + 8 + 4 + MAX_VESTING_GRANTS * (8 + 8 + 8 + 8);
+
+    pub fn new(user: Pubkey) -> Self {
+        VestingAccount {
+            user,
+            total: 0,
+            released: 0,
+            grants: Vec::new(),
+        }
+    }
+
+    //each grant keeps its own clock, so a new claim never delays earlier ones
+    pub fn add_grant(&mut self, grant: VestingGrant) -> Result<(), FaucetError> {
+        self.grants
+            .retain(|existing| existing.vested(grant.start) < existing.amount);
+        if self.grants.len() >= MAX_VESTING_GRANTS {
+            return Err(FaucetError::TooManyVestingGrants);
+        }
+
+        self.total = self.total.saturating_add(grant.amount);
+        self.grants.push(grant);
+        Ok(())
+    }
+
+    pub fn vested(&self, current_time: i64) -> u64 {
+        let unvested: u64 = self
+            .grants
+            .iter()
+            .map(|grant| grant.amount - grant.vested(current_time))
+            .sum();
+        self.total - unvested
+    }
+
+    pub fn releasable(&self, current_time: i64) -> u64 {
+        self.vested(current_time).saturating_sub(self.released)
+    }
+}
+
+//referral bonuses a referrer has earned but not withdrawn yet
+#[derive(BorshDeserialize, BorshSerialize, Debug)]
+pub struct ReferralAccount {
+    pub referrer: Pubkey,
+    pub claimable: u64,
+    pub total_earned: u64,
+    pub referrals: u64,
+}
+
+impl ReferralAccount {
+    pub const SPACE: usize = 32 + 8 + 8 + 8;
+}
+
+//tokens a delegated distributor may hand out from the treasury
+#[derive(BorshDeserialize, BorshSerialize, Debug)]
+pub struct DistributorAllowance {
+    pub distributor: Pubkey,
+    pub remaining: u64,
+    pub total_distributed: u64,
+    pub expires_at: Option<i64>, //no distributions from this unix time on
+}
+
+impl DistributorAllowance {
+    //fixed so a later grant with an expiry still fits
+    pub const SPACE: usize = 32 + 8 + 8 + 9;
+}
+
+//claim authorized off-chain, signed by the faucet's voucher authority
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, PartialEq)]
+pub struct ClaimVoucher {
+    pub faucet: Pubkey, //faucet config PDA, keeps vouchers from working on other deployments
+    pub user: Pubkey,
+    pub amount: u64,
+    pub nonce: u64,
+    pub expiry: i64, //unix time the voucher stops working
+}
+
+impl ClaimVoucher {
+    //bytes the voucher authority signs
+    pub fn message(&self) -> Vec<u8> {
+        borsh::to_vec(self).unwrap()
+    }
+}
+
+//marks a voucher nonce as spent
+#[derive(BorshDeserialize, BorshSerialize, Debug)]
+pub struct UsedVoucher {
+    pub user: Pubkey,
+    pub nonce: u64,
+    pub amount: u64,
+    pub used_at: i64,
+}
+
+impl UsedVoucher {
+    pub const SPACE: usize = 32 + 8 + 8 + 8;
+}
+
+//events emitted through the program logs (borsh encoded)
+#[derive(BorshDeserialize, BorshSerialize, Debug)]
+pub enum FaucetEvent {
+    Deposited {
+        donor: Pubkey,
+        amount: u64,
+        donor_total: u64,
+        faucet_total: u64,
+    },
+    FaucetExpired {
+        ends_at: i64,
+        closed_at: i64,
+    },
+    LowTreasury {
+        remaining: u64,
+        low_water_mark: u64,
+        paused: bool,
+    },
+    Paused {
+        admin: Pubkey,
+        flags: u8,
+        reason: u16,
+    },
+    Resumed {
+        admin: Pubkey,
+        flags: u8,
+        reason: u16,
+    },
+    AllowanceGranted {
+        distributor: Pubkey,
+        amount: u64,
+        expires_at: Option<i64>,
+    },
+    Distributed {
+        distributor: Pubkey,
+        recipient: Pubkey,
+        amount: u64,
+        remaining: u64,
+    },
+    AirdropCompleted {
+        requested: u8,
+        sent: u8,
+        skipped: Vec<u8>, //indexes of recipients that were not paid
+        total_amount: u64,
+    },
+    FeesWithdrawn {
+        treasurer: Pubkey,
+        destination: Pubkey,
+        amount: u64,
+    },
+    BondSlashed {
+        user: Pubkey,
+        amount: u64,
+    },
+    TokensReturned {
+        user: Pubkey,
+        amount: u64,
+        total_returned: u64,
+    },
+    ReferralCredited {
+        referrer: Pubkey,
+        user: Pubkey,
+        bonus: u64,
+        claimable: u64,
+    },
+    VestingReleased {
+        user: Pubkey,
+        amount: u64,
+        remaining: u64,
+    },
+    Claimed {
+        user: Pubkey,
+        amount: u64,
+        total_claims: u64,
+        lottery_tier: Option<u8>, //tier drawn when the faucet runs a lottery
+    },
+}
+
+//settings changed by UpdateFaucetConfig, None leaves a setting as it is
+//pause flags aren't here, EmergencyPause and Resume change them so every change is logged
+#[derive(BorshDeserialize, BorshSerialize, Debug, Default)]
+pub struct ConfigUpdate {
+    pub tokens_per_claim: Option<u64>,
+    pub cooldown_seconds: Option<i64>,
+    pub claim_mode: Option<ClaimMode>,
+    pub rate_limit: Option<RateLimitPolicy>,
+    pub cooldown_unit: Option<CooldownUnit>,
+    pub starts_at: Option<Option<i64>>,
+    pub ends_at: Option<Option<i64>>,
+    pub emission: Option<EmissionCurve>,
+    pub low_water_mark: Option<Option<u64>>,
+    pub pause_on_low_water: Option<bool>,
+    pub voucher_authority: Option<Option<Pubkey>>,
+    pub anti_bot: Option<bool>,
+    pub token_gate: Option<Option<TokenGate>>,
+    pub max_recipient_token_balance: Option<Option<u64>>,
+    pub max_recipient_lamports: Option<Option<u64>>,
+    pub claim_fee_lamports: Option<u64>,
+    pub treasurer: Option<Option<Pubkey>>,
+    pub claim_bond_lamports: Option<u64>,
+    pub bond_holding_seconds: Option<i64>,
+    pub streak: Option<Option<StreakPolicy>>,
+    pub referral_bonus: Option<u64>,
+    pub vesting: Option<Option<VestingSchedule>>,
+    pub basket: Option<Vec<BasketEntry>>,
+    pub lottery: Option<Option<Lottery>>,
+}
+
+//instructions program will accept
+//decoded once per instruction, so the large config update variant is fine
+#[allow(clippy::large_enum_variant)]
+#[derive(BorshDeserialize, BorshSerialize, Debug)]
+pub enum FaucetInstruction {
+    //initialize faucet
+    //accounts :
+    //signer -> admin account
+    //writable -> faucet config account
+    //token mint account
+    //system program
+    //faucet treasury token account (faucet mint, owned by the config PDA)
+    InitializeFaucet {
+        tokens_per_claim: u64,
+        cooldown_seconds: i64,
+    },
+    //claims tokens from faucet
+    //accounts :
+    //signer -> user req tokens
+    //writable -> user claim record PDA
+    //writable -> user token account
+    //writable -> faucet treasury token account
+    //writable -> faucet config account
+    //token program
+    //system program
+    //faucet authority (config PDA)
+    //optional signer, writable -> sponsor paying rent for a new claim record
+    //optional instructions sysvar (required when anti_bot is on)
+    //optional slot hashes sysvar (required when lottery is set)
+    //user token account for the gating mint (only when token_gate is set)
+    //referrer's claim record PDA, then writable -> referrer's referral PDA (only when referrer is set)
+    //writable -> fee vault PDA (only when claim_fee_lamports is set)
+    //writable -> vesting PDA, writable -> vesting escrow PDA, faucet mint (only when vesting is set)
+    //per basket entry : writable -> entry treasury, writable -> user token account for the entry mint
+    //amount -> None claims the maximum allowed
+    //referrer -> wallet that referred the user, only credited on their first claim
+    ClaimTokens {
+        amount: Option<u64>,
+        referrer: Option<Pubkey>,
+    },
+    //admin only, see ConfigUpdate
+    //accounts :
+    //signer -> admin account
+    //writable -> faucet config account
+    UpdateFaucetConfig(ConfigUpdate),
+    //pauses (or resumes) the faucet and records why
+    //accounts :
+    //signer -> admin account
+    //writable -> faucet config account
+    //writable -> pause log PDA
+    //system program
+    //flags -> PAUSE_* operations to stop
+    EmergencyPause {
+        flags: u8,
+        reason: u16,
+        memo: Option<String>,
+    },
+    WithdrawTreasury {
+        amount: u64,
+    },
+    //refills the treasury from anyone's token account
+    //accounts :
+    //signer -> donor
+    //writable -> donor contribution PDA
+    //writable -> donor token account
+    //writable -> faucet treasury token account
+    //writable -> faucet config account
+    //token program
+    //system program
+    Deposit {
+        amount: u64,
+    },
+    //deactivates the faucet once ends_at has passed (anyone can crank it)
+    //accounts :
+    //writable -> faucet config account
+    CloseExpiredFaucet,
+    //same accounts as EmergencyPause
+    //flags -> PAUSE_* operations to restart
+    Resume {
+        flags: u8,
+        reason: u16,
+        memo: Option<String>,
+    },
+    //sets how much a distributor may hand out (replaces any previous grant)
+    //accounts :
+    //signer -> admin account
+    //faucet config account
+    //writable -> distributor allowance PDA
+    //system program
+    GrantAllowance {
+        distributor: Pubkey,
+        amount: u64,
+        expires_at: Option<i64>,
+    },
+    //sends tokens from the treasury within the distributor's allowance
+    //accounts :
+    //signer -> distributor
+    //writable -> distributor allowance PDA
+    //writable -> recipient token account
+    //writable -> faucet treasury token account
+    //faucet config account (also the transfer authority)
+    //token program
+    Distribute {
+        recipient: Pubkey,
+        amount: u64,
+    },
+    //admin sends amounts[i] to the i-th recipient token account
+    //recipients that can't receive are skipped, the run stops if the treasury runs dry
+    //accounts :
+    //signer -> admin account
+    //writable -> faucet config account (also the transfer authority)
+    //writable -> faucet treasury token account
+    //token program
+    //writable -> recipient token accounts (one per amount)
+    Airdrop {
+        amounts: Vec<u64>,
+    },
+    //closes a user's claim record once it no longer limits them
+    //accounts :
+    //signer -> user
+    //writable -> user claim record PDA
+    //writable -> rent receiver (whoever paid for the record)
+    //faucet config account
+    CloseClaimRecord,
+    //claims the amount in a voucher signed off-chain by the voucher authority
+    //the preceding instruction must be the Ed25519 check of ClaimVoucher::message
+    //accounts :
+    //signer -> user
+    //writable -> used voucher PDA (user, nonce)
+    //writable -> user token account
+    //writable -> faucet treasury token account
+    //writable -> faucet config account
+    //token program
+    //system program
+    //instructions sysvar
+    //user claim record PDA (checked for bans, may not exist yet)
+    //optional signer, writable -> sponsor paying rent for the used voucher PDA
+    ClaimWithVoucher {
+        amount: u64,
+        nonce: u64,
+        expiry: i64,
+    },
+    //sweeps collected claim fees out of the fee vault
+    //accounts :
+    //signer -> treasurer (admin when no treasurer is set)
+    //faucet config account
+    //writable -> fee vault PDA
+    //writable -> destination for the lamports
+    //system program
+    WithdrawFees,
+    //returns the user's bond once the holding period has passed
+    //accounts :
+    //signer, writable -> user
+    //writable -> user claim record PDA
+    ReclaimBond,
+    //bans a user and moves their bond to the fee vault
+    //accounts :
+    //signer -> admin account
+    //faucet config account
+    //writable -> user claim record PDA
+    //writable -> fee vault PDA
+    SlashBond {
+        user: Pubkey,
+    },
+    //gives leftover tokens back to the treasury, earning rate limit credit
+    //accounts :
+    //signer -> user
+    //writable -> user claim record PDA
+    //writable -> user token account (source)
+    //writable -> faucet treasury token account
+    //faucet config account
+    //token program
+    ReturnTokens {
+        amount: u64,
+    },
+    //pays out a referrer's accumulated referral bonuses from the treasury
+    //accounts :
+    //signer -> referrer
+    //writable -> referral PDA
+    //writable -> referrer token account
+    //writable -> faucet treasury token account
+    //faucet config account
+    //token program
+    WithdrawReferralBonus,
+    //sends the vested part of the user's vesting claims to their token account
+    //accounts :
+    //signer -> user
+    //writable -> vesting PDA
+    //writable -> vesting escrow PDA
+    //writable -> user token account
+    //token program
+    Release,
+    //moves accounts written by the first release to the current layouts
+    //accounts :
+    //signer, writable -> admin account (pays rent for the extra space)
+    //writable -> faucet config account
+    //system program
+    //faucet treasury token account (pinned when the config is still in the first release layout)
+    //writable -> claim record PDAs still in the first release layout
+    Migrate,
+}
+
+#[derive(Debug)]
+pub enum FaucetError {
+    CooldownNotMet,
+    FaucetInactive,
+    InsufficientFunds,
+    UnauthorizedAdmin,
+    InvalidAmount,
+    AlreadyAtTarget,
+    InvalidConfig,
+    FaucetNotStarted,
+    FaucetEnded,
+    FaucetNotEnded,
+    EmissionExhausted,
+    MemoTooLong,
+    OperationPaused,
+    AllowanceExpired,
+    AllowanceExceeded,
+    AirdropTooLarge,
+    RecordInUse,
+    InvalidVoucher,
+    VoucherExpired,
+    VoucherAlreadyUsed,
+    ClaimViaCpi,
+    MultipleClaims,
+    TokenGateNotMet,
+    RecipientTokenBalanceTooHigh,
+    RecipientLamportsTooHigh,
+    UnauthorizedTreasurer,
+    UserBanned,
+    BondLocked,
+    InvalidReferral,
+    NothingToRelease,
+    TooManyVestingGrants,
+}
+
+impl From<FaucetError> for ProgramError {
+    fn from(e: FaucetError) -> Self {
+        match e {
+            FaucetError::CooldownNotMet => ProgramError::Custom(1000),
+            FaucetError::FaucetInactive => ProgramError::Custom(1001),
+            FaucetError::InsufficientFunds => ProgramError::Custom(1002),
+            FaucetError::UnauthorizedAdmin => ProgramError::Custom(1003),
+            FaucetError::InvalidAmount => ProgramError::Custom(1004),
+            FaucetError::AlreadyAtTarget => ProgramError::Custom(1005),
+            FaucetError::InvalidConfig => ProgramError::Custom(1006),
+            FaucetError::FaucetNotStarted => ProgramError::Custom(1007),
+            FaucetError::FaucetEnded => ProgramError::Custom(1008),
+            FaucetError::FaucetNotEnded => ProgramError::Custom(1009),
+            FaucetError::EmissionExhausted => ProgramError::Custom(1010),
+            FaucetError::MemoTooLong => ProgramError::Custom(1011),
+            FaucetError::OperationPaused => ProgramError::Custom(1012),
+            FaucetError::AllowanceExpired => ProgramError::Custom(1013),
+            FaucetError::AllowanceExceeded => ProgramError::Custom(1014),
+            FaucetError::AirdropTooLarge => ProgramError::Custom(1015),
+            FaucetError::RecordInUse => ProgramError::Custom(1016),
+            FaucetError::InvalidVoucher => ProgramError::Custom(1017),
+            FaucetError::VoucherExpired => ProgramError::Custom(1018),
+            FaucetError::VoucherAlreadyUsed => ProgramError::Custom(1019),
+            FaucetError::ClaimViaCpi => ProgramError::Custom(1020),
+            FaucetError::MultipleClaims => ProgramError::Custom(1021),
+            FaucetError::TokenGateNotMet => ProgramError::Custom(1022),
+            FaucetError::RecipientTokenBalanceTooHigh => ProgramError::Custom(1023),
+            FaucetError::RecipientLamportsTooHigh => ProgramError::Custom(1024),
+            FaucetError::UnauthorizedTreasurer => ProgramError::Custom(1025),
+            FaucetError::UserBanned => ProgramError::Custom(1026),
+            FaucetError::BondLocked => ProgramError::Custom(1027),
+            FaucetError::InvalidReferral => ProgramError::Custom(1028),
+            FaucetError::NothingToRelease => ProgramError::Custom(1029),
+            FaucetError::TooManyVestingGrants => ProgramError::Custom(1030),
+        }
+    }
+}
+
+//reads borsh state from an account that may have unused trailing space
+pub fn load_state<T: BorshDeserialize>(data: &[u8]) -> Result<T, ProgramError> {
+    T::deserialize(&mut &data[..]).map_err(|_| ProgramError::InvalidAccountData)
+}
+
+//builds Airdrop instructions for any number of recipients, MAX_AIRDROP_RECIPIENTS at a time
+//(for clients, each instruction should go in its own transaction)
+pub fn airdrop_instructions(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    treasury: &Pubkey,
+    recipients: &[(Pubkey, u64)],
+) -> Vec<Instruction> {
+    let (faucet_config_pda, _) = Pubkey::find_program_address(&[b"faucet_config"], program_id);
+
+    recipients
+        .chunks(MAX_AIRDROP_RECIPIENTS)
+        .map(|chunk| {
+            let mut accounts = vec![
+                AccountMeta::new_readonly(*admin, true),
+                AccountMeta::new(faucet_config_pda, false),
+                AccountMeta::new(*treasury, false),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            ];
+            accounts.extend(
+                chunk
+                    .iter()
+                    .map(|(token_account, _)| AccountMeta::new(*token_account, false)),
+            );
+
+            Instruction::new_with_borsh(
+                *program_id,
+                &FaucetInstruction::Airdrop {
+                    amounts: chunk.iter().map(|(_, amount)| *amount).collect(),
+                },
+                accounts,
+            )
+        })
+        .collect()
+}
+
+//a treasury must hold the faucet mint and be spendable by the config PDA alone
+fn validate_treasury(
+    treasury_account: &AccountInfo,
+    token_mint: &Pubkey,
+    faucet_config_pda: &Pubkey,
+) -> ProgramResult {
+    if *treasury_account.owner != TOKEN_PROGRAM_ID {
+        msg!("Faucet treasury must be owned by the token program");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let treasury_data = spl_token::state::Account::unpack(&treasury_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    if treasury_data.mint != *token_mint
+        || treasury_data.owner != *faucet_config_pda
+        || treasury_data.delegate.is_some()
+        || treasury_data.close_authority.is_some()
+    {
+        msg!("Faucet treasury must hold the faucet mint and be controlled by the faucet only");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    Ok(())
+}
+
+//takes the next account only if it matches, for optional trailing accounts
+fn next_optional_account<'a, 'b>(
+    accounts_iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+    matches: impl Fn(&AccountInfo<'b>) -> bool,
+) -> Option<&'a AccountInfo<'b>> {
+    let next = accounts_iter.clone().next()?;
+    if matches(next) {
+        accounts_iter.next()
+    } else {
+        None
+    }
+}
+
+//checks the instruction right before this one is an Ed25519 signature by signer over message
+fn verify_ed25519_signature(
+    instructions_account: &AccountInfo,
+    signer: &Pubkey,
+    message: &[u8],
+) -> Result<(), ProgramError> {
+    if *instructions_account.key != instructions_sysvar::ID {
+        msg!("Instructions sysvar account is not the correct account");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let current_index = load_current_index_checked(instructions_account)?;
+    if current_index == 0 {
+        msg!("Voucher must follow an Ed25519 signature instruction");
+        return Err(FaucetError::InvalidVoucher.into());
+    }
+
+    let signature_instruction =
+        load_instruction_at_checked(current_index as usize - 1, instructions_account)?;
+    if signature_instruction.program_id != ed25519_program::ID {
+        msg!("Voucher must follow an Ed25519 signature instruction");
+        return Err(FaucetError::InvalidVoucher.into());
+    }
+
+    //layout : count u8, padding u8, then 7 u16 offsets per signature
+    let data = &signature_instruction.data;
+    if data.len() < 16 || data[0] != 1 {
+        msg!("Ed25519 instruction must verify exactly one signature");
+        return Err(FaucetError::InvalidVoucher.into());
+    }
+
+    let offset = |i: usize| u16::from_le_bytes([data[2 + i * 2], data[3 + i * 2]]);
+    let (signature_index, public_key_offset, public_key_index) = (offset(1), offset(2), offset(3));
+    let (message_offset, message_size, message_index) = (offset(4), offset(5), offset(6));
+
+    //u16::MAX means the data lives in the Ed25519 instruction itself
+    if signature_index != u16::MAX || public_key_index != u16::MAX || message_index != u16::MAX {
+        msg!("Ed25519 instruction must carry its own signature, key and message");
+        return Err(FaucetError::InvalidVoucher.into());
+    }
+
+    let public_key = data.get(public_key_offset as usize..public_key_offset as usize + 32);
+    let signed_message =
+        data.get(message_offset as usize..message_offset as usize + message_size as usize);
+
+    if public_key != Some(signer.as_ref()) || signed_message != Some(message) {
+        msg!("Voucher was not signed by the voucher authority");
+        return Err(FaucetError::InvalidVoucher.into());
+    }
+
+    Ok(())
+}
+
+//rejects claims made through CPI or bundled with other claims in one transaction
+fn check_claim_transaction(
+    program_id: &Pubkey,
+    instructions_account: &AccountInfo,
+) -> ProgramResult {
+    //the sysvar only lists top level instructions, so a CPI shows the caller's instruction
+    let current_index = load_current_index_checked(instructions_account)?;
+    let current = load_instruction_at_checked(current_index as usize, instructions_account)?;
+    if current.program_id != *program_id {
+        msg!("Claims must not be made through {}", current.program_id);
+        return Err(FaucetError::ClaimViaCpi.into());
+    }
+
+    let mut claims = 0;
+    let mut index = 0;
+    while let Ok(instruction) = load_instruction_at_checked(index, instructions_account) {
+        if instruction.program_id == *program_id
+            && matches!(
+                FaucetInstruction::try_from_slice(&instruction.data),
+                Ok(FaucetInstruction::ClaimTokens { .. }
+                    | FaucetInstruction::ClaimWithVoucher { .. })
+            )
+        {
+            claims += 1;
+        }
+        index += 1;
+    }
+
+    if claims > 1 {
+        msg!(
+            "Transaction contains {} claims, only one is allowed",
+            claims
+        );
+        return Err(FaucetError::MultipleClaims.into());
+    }
+
+    Ok(())
+}
+
+//eligibility every claim path shares : pause, anti bot, claim window and bans
+fn check_claim_eligibility(
+    program_id: &Pubkey,
+    faucet_config: &FaucetConfig,
+    user: &Pubkey,
+    user_claim_record_account: &AccountInfo,
+    instructions_account: Option<&AccountInfo>,
+    current_time: i64,
+) -> ProgramResult {
+    if faucet_config.is_paused(PAUSE_CLAIMS) {
+        msg!("Faucet is currently inactive");
+        return Err(FaucetError::FaucetInactive.into());
+    }
+
+    if faucet_config.anti_bot {
+        let Some(instructions_account) = instructions_account else {
+            msg!("Anti bot checks need the instructions sysvar account");
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        check_claim_transaction(program_id, instructions_account)?;
+    }
+
+    if let Some(starts_at) = faucet_config.starts_at
+        && current_time < starts_at
+    {
+        msg!("Faucet opens in {} seconds", starts_at - current_time);
+        return Err(FaucetError::FaucetNotStarted.into());
+    }
+
+    if faucet_config.has_ended(current_time) {
+        msg!("Faucet closed at {:?}", faucet_config.ends_at);
+        return Err(FaucetError::FaucetEnded.into());
+    }
+
+    let (user_claim_pda, _user_bump_seed) =
+        Pubkey::find_program_address(&[b"user_claim", user.as_ref()], program_id);
+
+    if user_claim_pda != *user_claim_record_account.key {
+        msg!("User claim record account is not the correct PDA");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    //no record yet means the user never claimed, so can't have been banned
+    if user_claim_record_account.data_len() > 0
+        && load_state::<UserClaimedRecord>(&user_claim_record_account.data.borrow())?.banned
+    {
+        msg!("User {} is banned from this faucet", user);
+        return Err(FaucetError::UserBanned.into());
+    }
+
+    Ok(())
+}
+
+//writes an event to the logs so indexers can pick it up
+fn emit_event(event: &FaucetEvent) -> ProgramResult {
+    sol_log_data(&[&borsh::to_vec(event)?]);
+    Ok(())
+}
+
+//emits LowTreasury once a payout leaves the treasury under the low water mark,
+//pausing claims too if the faucet is set up to
+fn check_low_water_mark(
+    faucet_config: &mut FaucetConfig,
+    faucet_config_account: &AccountInfo,
+    remaining: u64,
+) -> ProgramResult {
+    let Some(low_water_mark) = faucet_config.low_water_mark else {
+        return Ok(());
+    };
+
+    if remaining >= low_water_mark {
+        return Ok(());
+    }
+
+    msg!(
+        "Treasury is low: {} tokens left (low water mark {})",
+        remaining,
+        low_water_mark
+    );
+
+    if faucet_config.pause_on_low_water {
+        faucet_config.paused |= PAUSE_CLAIMS;
+        faucet_config.serialize(&mut &mut faucet_config_account.data.borrow_mut()[..])?;
+        msg!("Faucet paused until the treasury is refilled!");
+    }
+
+    emit_event(&FaucetEvent::LowTreasury {
+        remaining,
+        low_water_mark,
+        paused: faucet_config.pause_on_low_water,
+    })
+}
+
+//zero pads a memo into its fixed size slot
+fn pack_memo(memo: Option<&str>) -> Result<[u8; PAUSE_MEMO_LEN], FaucetError> {
+    let memo = memo.unwrap_or_default().as_bytes();
+    if memo.len() > PAUSE_MEMO_LEN {
+        msg!("Memo can be at most {} bytes", PAUSE_MEMO_LEN);
+        return Err(FaucetError::MemoTooLong);
+    }
+
+    let mut padded = [0u8; PAUSE_MEMO_LEN];
+    padded[..memo.len()].copy_from_slice(memo);
+    Ok(padded)
+}
+
+//records a pause/resume on the pause log PDA, creating it on first use
+fn append_pause_log<'a>(
+    program_id: &Pubkey,
+    admin_account: &AccountInfo<'a>,
+    pause_log_account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    entry: PauseLogEntry,
+) -> ProgramResult {
+    let pause_log_seed = b"pause_log";
+    let (pause_log_pda, pause_log_bump) =
+        Pubkey::find_program_address(&[pause_log_seed], program_id);
+
+    if pause_log_pda != *pause_log_account.key {
+        msg!("Pause log account is not the correct PDA");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let mut pause_log = if pause_log_account.data_len() == 0 {
+        let pause_log = PauseLog {
+            entries: [entry; PAUSE_LOG_LEN],
+            len: 0,
+            next: 0,
+        };
+
+        let required_space = borsh::to_vec(&pause_log)?.len();
+        let required_lamports = Rent::get()?.minimum_balance(required_space);
+
+        let create_account_instruction = system_instruction::create_account(
+            admin_account.key,
+            pause_log_account.key,
+            required_lamports,
+            required_space as u64,
+            program_id,
+        );
+
+        invoke_signed(
+            &create_account_instruction,
+            &[
+                admin_account.clone(),
+                pause_log_account.clone(),
+                system_program.clone(),
+            ],
+            &[&[pause_log_seed, &[pause_log_bump]]],
+        )?;
+        pause_log
+    } else {
+        PauseLog::try_from_slice(&pause_log_account.data.borrow())?
+    };
+
+    pause_log.push(entry);
+    pause_log.serialize(&mut &mut pause_log_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+//grows a program owned account to new_len, topping up rent from payer
+fn grow_account<'a>(
+    account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    new_len: usize,
+) -> ProgramResult {
+    let required_lamports = Rent::get()?.minimum_balance(new_len);
+    let missing = required_lamports.saturating_sub(account.lamports());
+    if missing > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, account.key, missing),
+            &[payer.clone(), account.clone(), system_program.clone()],
+        )?;
+    }
+    account.resize(new_len)
+}
+
+entrypoint!(process_instruction);
+
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = FaucetInstruction::try_from_slice(instruction_data)?;
+
+    match instruction {
+        FaucetInstruction::InitializeFaucet {
+            tokens_per_claim,
+            cooldown_seconds,
+        } => {
+            msg!(
+                "Initializing faucet with {} tokens per claim, {} second cooldown",
+                tokens_per_claim,
+                cooldown_seconds
+            );
+
+            let accounts_iter = &mut accounts.iter();
+
+            //signer (admin account)
+            let admin_account = next_account_info(accounts_iter)?;
+            if !admin_account.is_signer {
+                msg!("Admin account must be a signer");
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+
+            //faucet config account (PDA creator)
+            let faucet_config_account = next_account_info(accounts_iter)?;
+
+            //token mint account (valid SOL token mint)
+            let token_mint_account = next_account_info(accounts_iter)?;
+
+            //system program (required to create accounts)
+            let system_program = next_account_info(accounts_iter)?;
+
+            //token account all payouts come from, pinned in the config
+            let faucet_treasury_account = next_account_info(accounts_iter)?;
+
+            //validate that token mint is actually a mint account (account that stores global metadata about a token)
+            let mint_data = Mint::unpack(&token_mint_account.data.borrow())
+                .map_err(|_| ProgramError::InvalidAccountData)?;
+            msg!("Token mint validated. Supply {}", mint_data.supply);
+
+            //create PDA for faucet config
+            let faucet_config_seed = b"faucet_config";
+            let (faucet_config_pda, bump_seed) =
+                Pubkey::find_program_address(&[faucet_config_seed], program_id);
+
+            // Verify the passed account is the correct PDA
+            if faucet_config_pda != *faucet_config_account.key {
+                msg!("Faucet config account is not the correct PDA");
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            validate_treasury(
+                faucet_treasury_account,
+                token_mint_account.key,
+                &faucet_config_pda,
+            )?;
+
+            let config_data = FaucetConfig::new(
+                *admin_account.key,
+                *token_mint_account.key,
+                *faucet_treasury_account.key,
+                tokens_per_claim,
+                cooldown_seconds,
+                Clock::get()?.unix_timestamp,
+            );
+
+            let required_space = FaucetConfig::SPACE;
+
+            //lamports for rent exemption
+            let rent = Rent::get()?;
+            let required_lamports = rent.minimum_balance(required_space);
+
+            //creating the faucet config
+            let create_account_instruction = system_instruction::create_account(
+                admin_account.key,         //payer
+                faucet_config_account.key, //new account
+                required_lamports,         //lamports to transfer
+                required_space as u64,     //allocate the space
+                program_id,                //owner of new account
+            );
+
+            //invoke system program to create the new account (CPI)
+            invoke_signed(
+                &create_account_instruction,
+                &[
+                    admin_account.clone(),
+                    faucet_config_account.clone(),
+                    system_program.clone(),
+                ],
+                &[&[faucet_config_seed, &[bump_seed]]], //PDA sign
+            )?;
+
+            //serialize and store the config of faucet
+            config_data.serialize(&mut &mut faucet_config_account.data.borrow_mut()[..])?;
+
+            msg!("Faucet initialized successfully!");
+            msg!("Admin: {}", admin_account.key);
+            msg!("Token Mint: {}", token_mint_account.key);
+            msg!("Treasury: {}", faucet_treasury_account.key);
+            msg!("PDA of Faucet: {}", faucet_config_pda);
+        }
+
+        FaucetInstruction::ClaimTokens { amount, referrer } => {
+            msg!("Processing claim tokens request");
+
+            //account iterator
+            let accounts_iter = &mut accounts.iter();
+
+            //user requesting tokens (must be a signer)
+            let user_account = next_account_info(accounts_iter)?;
+            if !user_account.is_signer {
+                msg!("User account must be a signer");
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+
+            //user's claim record PDA(will create and update)
+            let user_claim_record_account = next_account_info(accounts_iter)?;
+
+            //token account of user (to receive the tokens)
+            let user_token_account = next_account_info(accounts_iter)?;
+
+            // faucet treasury account token (tokens come from this)
+            let faucet_treasury_account = next_account_info(accounts_iter)?;
+
+            //faucet config account (contain settings)
+            let faucet_account_config = next_account_info(accounts_iter)?;
+
+            // token program
+            let token_program = next_account_info(accounts_iter)?;
+
+            //system program (needed to create user claim record if first time)
+            let system_program = next_account_info(accounts_iter)?;
+
+            //create PDA for faucet config
+            let faucet_config_seed = b"faucet_config";
+            let (faucet_config_pda, faucet_bump_seed) =
+                Pubkey::find_program_address(&[faucet_config_seed], program_id);
+
+            //the config decides what the PDA signs for, so it has to be the real one
+            if faucet_config_pda != *faucet_account_config.key
+                || faucet_account_config.owner != program_id
+            {
+                msg!("Faucet config account is not the correct PDA");
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            //load faucet config
+            let mut faucet_config =
+                load_state::<FaucetConfig>(&faucet_account_config.data.borrow())?;
+            faucet_config.check_treasury(faucet_treasury_account)?;
+
+            let faucet_authority_account = next_account_info(accounts_iter)?; // Add this line
+
+            //optional sponsor so fresh wallets without SOL can claim
+            let sponsor_account = next_optional_account(accounts_iter, |account| account.is_signer);
+            let rent_payer_account = sponsor_account.unwrap_or(user_account);
+
+            let instructions_account = next_optional_account(accounts_iter, |account| {
+                *account.key == instructions_sysvar::ID
+            });
+
+            let slot_hashes_account =
+                next_optional_account(accounts_iter, |account| *account.key == slot_hashes::ID);
+
+            //getting the current timestamp
+            let clock = Clock::get()?;
+            let current_time = clock.unix_timestamp;
+
+            //paused, bot, outside the claim window or banned
+            check_claim_eligibility(
+                program_id,
+                &faucet_config,
+                user_account.key,
+                user_claim_record_account,
+                instructions_account,
+                current_time,
+            )?;
+
+            //balance caps and top ups read this account, so it has to be a real token account
+            if *user_token_account.owner != TOKEN_PROGRAM_ID {
+                msg!("User token account must be owned by the token program");
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            let user_token_data =
+                spl_token::state::Account::unpack(&user_token_account.data.borrow())
+                    .map_err(|_| ProgramError::InvalidAccountData)?;
+
+            if user_token_data.mint != faucet_config.token_mint
+                || user_token_data.owner != *user_account.key
+            {
+                msg!("User token account must hold the faucet mint and be owned by the user");
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            //the faucet is for wallets that actually need tokens
+            if let Some(max_balance) = faucet_config.max_recipient_token_balance
+                && user_token_data.amount > max_balance
+            {
+                msg!(
+                    "User holds {} tokens, claims are limited to wallets holding at most {}",
+                    user_token_data.amount,
+                    max_balance
+                );
+                return Err(FaucetError::RecipientTokenBalanceTooHigh.into());
+            }
+
+            if let Some(max_lamports) = faucet_config.max_recipient_lamports
+                && user_account.lamports() > max_lamports
+            {
+                msg!(
+                    "User holds {} lamports, claims are limited to wallets holding at most {}",
+                    user_account.lamports(),
+                    max_lamports
+                );
+                return Err(FaucetError::RecipientLamportsTooHigh.into());
+            }
+
+            //holders only faucet
+            if let Some(token_gate) = faucet_config.token_gate {
+                let gate_token_account = next_account_info(accounts_iter)?;
+                if *gate_token_account.owner != TOKEN_PROGRAM_ID {
+                    msg!("Gating token account must be owned by the token program");
+                    return Err(ProgramError::InvalidAccountData);
+                }
+
+                let gate_token_data =
+                    spl_token::state::Account::unpack(&gate_token_account.data.borrow())
+                        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+                if gate_token_data.mint != token_gate.mint
+                    || gate_token_data.owner != *user_account.key
+                {
+                    msg!("Gating token account must hold the gating mint and be owned by the user");
+                    return Err(ProgramError::InvalidAccountData);
+                }
+
+                if gate_token_data.amount < token_gate.min_balance {
+                    msg!(
+                        "Claims need at least {} of the gating token, user holds {}",
+                        token_gate.min_balance,
+                        gate_token_data.amount
+                    );
+                    return Err(FaucetError::TokenGateNotMet.into());
+                }
+            }
+
+            //PDA for user claim record, already checked with the eligibility
+            let user_claim_seed = b"user_claim";
+            let (_user_claim_pda, user_bump_seed) = Pubkey::find_program_address(
+                &[user_claim_seed, user_account.key.as_ref()], //converts Pubkey to &[u8]
+                program_id,
+            );
+
+            let current_tick = faucet_config.cooldown_unit.current_tick(&clock);
+
+            //to check if the user's claim record exist
+            let first_claim = user_claim_record_account.data_len() == 0;
+            let mut user_record = if first_claim {
+                msg!("User is claiming for the first time... Creating a new account!");
+
+                //creating the user claim record account
+                let mut user_record = UserClaimedRecord::new(*user_account.key);
+                user_record.rate_limit_unit = faucet_config.cooldown_unit;
+                user_record.rent_payer = *rent_payer_account.key;
+
+                let required_space = UserClaimedRecord::SPACE;
+                let rent = Rent::get()?;
+                let required_lamports = rent.minimum_balance(required_space);
+
+                let create_account_instruction = system_instruction::create_account(
+                    rent_payer_account.key,
+                    user_claim_record_account.key,
+                    required_lamports,
+                    required_space as u64,
+                    program_id,
+                );
+
+                invoke_signed(
+                    &create_account_instruction,
+                    &[
+                        rent_payer_account.clone(),
+                        user_claim_record_account.clone(),
+                        system_program.clone(),
+                    ],
+                    &[&[
+                        user_claim_seed,
+                        user_account.key.as_ref(),
+                        &[user_bump_seed],
+                    ]],
+                )?;
+                user_record
+            } else {
+                //load existing user record
+                load_state::<UserClaimedRecord>(&user_claim_record_account.data.borrow())?
+            };
+
+            //referrals only count on the claim that creates the user's record
+            if let Some(referrer) = referrer {
+                let referrer_record_account = next_account_info(accounts_iter)?;
+                let referral_account = next_account_info(accounts_iter)?;
+
+                let (referrer_claim_pda, _referrer_bump_seed) =
+                    Pubkey::find_program_address(&[user_claim_seed, referrer.as_ref()], program_id);
+
+                if referrer_claim_pda != *referrer_record_account.key {
+                    msg!("Referrer claim record account is not the correct PDA");
+                    return Err(ProgramError::InvalidAccountData);
+                }
+
+                if referrer == *user_account.key {
+                    msg!("Users can't refer themselves");
+                    return Err(FaucetError::InvalidReferral.into());
+                }
+
+                //a referrer must have claimed before, so referrals only point to older
+                //records and can't form loops
+                if first_claim && referrer_record_account.data_len() == 0 {
+                    msg!("{} hasn't claimed yet, claim goes unreferred", referrer);
+                } else if first_claim {
+                    let referrer_record =
+                        load_state::<UserClaimedRecord>(&referrer_record_account.data.borrow())?;
+
+                    if referrer_record.referrer == Some(*user_account.key) || referrer_record.banned
+                    {
+                        msg!("{} can't refer {}", referrer, user_account.key);
+                        return Err(FaucetError::InvalidReferral.into());
+                    }
+
+                    user_record.referrer = Some(referrer);
+                    msg!("Referred by {}", referrer);
+
+                    if faucet_config.referral_bonus > 0 {
+                        let referral_seed = b"referral";
+                        let (referral_pda, referral_bump_seed) = Pubkey::find_program_address(
+                            &[referral_seed, referrer.as_ref()],
+                            program_id,
+                        );
+
+                        if referral_pda != *referral_account.key {
+                            msg!("Referral account is not the correct PDA");
+                            return Err(ProgramError::InvalidAccountData);
+                        }
+
+                        let mut referral = if referral_account.data_len() == 0 {
+                            let rent = Rent::get()?;
+                            invoke_signed(
+                                &system_instruction::create_account(
+                                    rent_payer_account.key,
+                                    referral_account.key,
+                                    rent.minimum_balance(ReferralAccount::SPACE),
+                                    ReferralAccount::SPACE as u64,
+                                    program_id,
+                                ),
+                                &[
+                                    rent_payer_account.clone(),
+                                    referral_account.clone(),
+                                    system_program.clone(),
+                                ],
+                                &[&[referral_seed, referrer.as_ref(), &[referral_bump_seed]]],
+                            )?;
+                            ReferralAccount {
+                                referrer,
+                                claimable: 0,
+                                total_earned: 0,
+                                referrals: 0,
+                            }
+                        } else {
+                            load_state::<ReferralAccount>(&referral_account.data.borrow())?
+                        };
+
+                        referral.claimable = referral
+                            .claimable
+                            .saturating_add(faucet_config.referral_bonus);
+                        referral.total_earned = referral
+                            .total_earned
+                            .saturating_add(faucet_config.referral_bonus);
+                        referral.referrals += 1;
+                        referral.serialize(&mut &mut referral_account.data.borrow_mut()[..])?;
+
+                        emit_event(&FaucetEvent::ReferralCredited {
+                            referrer,
+                            user: *user_account.key,
+                            bonus: faucet_config.referral_bonus,
+                            claimable: referral.claimable,
+                        })?;
+                    }
+                }
+            }
+
+            //history measured in another unit is carried over, switching must not grant a claim
+            if user_record.rate_limit_unit != faucet_config.cooldown_unit {
+                user_record.convert_rate_limit(faucet_config.cooldown_unit, &clock);
+            }
+
+            let treasury_data =
+                spl_token::state::Account::unpack(&faucet_treasury_account.data.borrow())
+                    .map_err(|_| ProgramError::InvalidAccountData)?;
+
+            //emission curve decides the current tokens per claim
+            let emission_start = faucet_config.starts_at.unwrap_or(faucet_config.created_at);
+            let tokens_per_claim = faucet_config.emission.evaluate(
+                faucet_config.tokens_per_claim,
+                current_time - emission_start,
+                treasury_data.amount,
+            );
+
+            if tokens_per_claim == 0 {
+                msg!("Emission curve has decayed to zero tokens per claim");
+                return Err(FaucetError::EmissionExhausted.into());
+            }
+
+            //allowances decay with the curve, so smaller claims can't be made up with more of them
+            let emitted_per_claim = tokens_per_claim;
+            let rate_limit = faucet_config
+                .rate_limit
+                .decayed(faucet_config.tokens_per_claim, emitted_per_claim);
+
+            let max_amount = faucet_config
+                .claim_mode
+                .claim_amount(tokens_per_claim, user_token_data.amount);
+
+            //rejecting here keeps the cooldown untouched
+            if max_amount == 0 {
+                msg!(
+                    "User already holds {} tokens, nothing to top up",
+                    user_token_data.amount
+                );
+                return Err(FaucetError::AlreadyAtTarget.into());
+            }
+
+            //a single claim can never exceed what the rate limit allows at once
+            let max_amount = max_amount.min(rate_limit.max_claim(emitted_per_claim));
+
+            //the rate limit is charged for the base claim, lottery prizes and streak boosts come on top
+            let charged_amount = match (&faucet_config.lottery, amount) {
+                (Some(_), Some(_)) => {
+                    msg!("Lottery faucets draw the claim amount, it can't be requested");
+                    return Err(FaucetError::InvalidAmount.into());
+                }
+                //a draw that only cost part of a claim could be rerolled until it hits the jackpot
+                (Some(_), None) => emitted_per_claim.min(rate_limit.max_claim(emitted_per_claim)),
+                (None, requested) => {
+                    let requested = requested.unwrap_or(max_amount);
+                    if requested == 0 || requested > max_amount {
+                        msg!(
+                            "Requested {} tokens, claims must be between 1 and {}",
+                            requested,
+                            max_amount
+                        );
+                        return Err(FaucetError::InvalidAmount.into());
+                    }
+                    requested
+                }
+            };
+
+            //checking the faucet's rate limit policy
+            if let Err(wait) = rate_limit.try_consume(
+                faucet_config.cooldown_seconds,
+                emitted_per_claim,
+                &mut user_record,
+                current_tick,
+                charged_amount,
+            ) {
+                msg!(
+                    "Cooldown period not met! Please wait for {} {}",
+                    wait,
+                    faucet_config.cooldown_unit.name()
+                );
+                return Err(FaucetError::CooldownNotMet.into());
+            }
+
+            //lottery faucets draw the amount instead
+            let mut lottery_tier = None;
+            let claim_amount = match &faucet_config.lottery {
+                Some(lottery) => {
+                    let Some(slot_hashes_account) = slot_hashes_account else {
+                        msg!("Lottery claims need the slot hashes sysvar account");
+                        return Err(ProgramError::NotEnoughAccountKeys);
+                    };
+
+                    //layout : entry count u64, then (slot u64, hash) newest first
+                    let slot_hashes_data = slot_hashes_account.data.borrow();
+                    let Some(recent_hash) = slot_hashes_data.get(16..48) else {
+                        msg!("Slot hashes sysvar has no entries yet");
+                        return Err(ProgramError::InvalidAccountData);
+                    };
+
+                    let seed =
+                        Lottery::seed(recent_hash, user_account.key, user_record.total_claims);
+                    let (tier, drawn) = lottery.draw(seed);
+                    lottery_tier = Some(tier);
+
+                    //prizes decay with the emission curve like every other claim
+                    let drawn = (drawn as u128 * emitted_per_claim as u128
+                        / faucet_config.tokens_per_claim as u128)
+                        as u64;
+
+                    //tiers are checked against the rate limit when set, so this only trips on a bad config
+                    if drawn > rate_limit.max_claim(emitted_per_claim) {
+                        msg!(
+                            "Lottery tier {} pays {} tokens, more than a single claim allows",
+                            tier,
+                            drawn
+                        );
+                        return Err(FaucetError::InvalidConfig.into());
+                    }
+
+                    msg!("Lottery drew tier {}: {} tokens", tier, drawn);
+                    drawn
+                }
+                None => charged_amount,
+            };
+
+            //loyalty multiplier for claiming in consecutive periods
+            let claim_amount = match &faucet_config.streak {
+                Some(streak) => {
+                    streak.record_claim(&mut user_record, current_time);
+                    msg!("Claim streak: {} periods", user_record.streak);
+                    streak.apply(claim_amount, user_record.streak)
+                }
+                None => claim_amount,
+            };
+
+            msg!(
+                "Cooldown check passed! Checking if {} tokens available...",
+                claim_amount
+            );
+
+            if treasury_data.amount < claim_amount {
+                msg!(
+                    "Treasury has {} tokens, but {} tokens requested!",
+                    treasury_data.amount,
+                    claim_amount
+                );
+                return Err(FaucetError::InsufficientFunds.into());
+            }
+
+            //anti spam fee, paid by the claimer even when a sponsor pays rent
+            if faucet_config.claim_fee_lamports > 0 {
+                let fee_vault_account = next_account_info(accounts_iter)?;
+                let (fee_vault_pda, _fee_vault_bump_seed) =
+                    Pubkey::find_program_address(&[b"fee_vault"], program_id);
+
+                if fee_vault_pda != *fee_vault_account.key {
+                    msg!("Fee vault account is not the correct PDA");
+                    return Err(ProgramError::InvalidAccountData);
+                }
+
+                invoke(
+                    &system_instruction::transfer(
+                        user_account.key,
+                        fee_vault_account.key,
+                        faucet_config.claim_fee_lamports,
+                    ),
+                    &[
+                        user_account.clone(),
+                        fee_vault_account.clone(),
+                        system_program.clone(),
+                    ],
+                )?;
+                msg!("Claim fee: {} lamports", faucet_config.claim_fee_lamports);
+            }
+
+            //refundable bond, held on the claim record until the holding period passes
+            if faucet_config.claim_bond_lamports > 0 {
+                invoke(
+                    &system_instruction::transfer(
+                        user_account.key,
+                        user_claim_record_account.key,
+                        faucet_config.claim_bond_lamports,
+                    ),
+                    &[
+                        user_account.clone(),
+                        user_claim_record_account.clone(),
+                        system_program.clone(),
+                    ],
+                )?;
+
+                user_record.bonded_lamports = user_record
+                    .bonded_lamports
+                    .saturating_add(faucet_config.claim_bond_lamports);
+                user_record.bond_unlocks_at =
+                    current_time.saturating_add(faucet_config.bond_holding_seconds);
+                msg!(
+                    "Bonded {} lamports until {}",
+                    user_record.bonded_lamports,
+                    user_record.bond_unlocks_at
+                );
+            }
+
+            //vesting faucets park the claim in the user's escrow instead
+            let destination_account = match faucet_config.vesting {
+                Some(schedule) => {
+                    let vesting_account = next_account_info(accounts_iter)?;
+                    let vesting_escrow_account = next_account_info(accounts_iter)?;
+                    let token_mint_account = next_account_info(accounts_iter)?;
+
+                    let vesting_seed = b"vesting";
+                    let (vesting_pda, vesting_bump_seed) = Pubkey::find_program_address(
+                        &[vesting_seed, user_account.key.as_ref()],
+                        program_id,
+                    );
+                    let vesting_escrow_seed = b"vesting_escrow";
+                    let (vesting_escrow_pda, vesting_escrow_bump_seed) =
+                        Pubkey::find_program_address(
+                            &[vesting_escrow_seed, user_account.key.as_ref()],
+                            program_id,
+                        );
+
+                    if vesting_pda != *vesting_account.key
+                        || vesting_escrow_pda != *vesting_escrow_account.key
+                    {
+                        msg!("Vesting accounts are not the correct PDAs");
+                        return Err(ProgramError::InvalidAccountData);
+                    }
+
+                    let mut vesting = if vesting_account.data_len() == 0 {
+                        if *token_mint_account.key != faucet_config.token_mint {
+                            msg!("Token mint account doesn't match the faucet mint");
+                            return Err(ProgramError::InvalidAccountData);
+                        }
+
+                        let rent = Rent::get()?;
+                        invoke_signed(
+                            &system_instruction::create_account(
+                                rent_payer_account.key,
+                                vesting_account.key,
+                                rent.minimum_balance(VestingAccount::SPACE),
+                                VestingAccount::SPACE as u64,
+                                program_id,
+                            ),
+                            &[
+                                rent_payer_account.clone(),
+                                vesting_account.clone(),
+                                system_program.clone(),
+                            ],
+                            &[&[
+                                vesting_seed,
+                                user_account.key.as_ref(),
+                                &[vesting_bump_seed],
+                            ]],
+                        )?;
+
+                        //escrow token account at its own PDA, spendable by the vesting PDA
+                        invoke_signed(
+                            &system_instruction::create_account(
+                                rent_payer_account.key,
+                                vesting_escrow_account.key,
+                                rent.minimum_balance(spl_token::state::Account::LEN),
+                                spl_token::state::Account::LEN as u64,
+                                &TOKEN_PROGRAM_ID,
+                            ),
+                            &[
+                                rent_payer_account.clone(),
+                                vesting_escrow_account.clone(),
+                                system_program.clone(),
+                            ],
+                            &[&[
+                                vesting_escrow_seed,
+                                user_account.key.as_ref(),
+                                &[vesting_escrow_bump_seed],
+                            ]],
+                        )?;
+                        invoke(
+                            &spl_token::instruction::initialize_account3(
+                                &TOKEN_PROGRAM_ID,
+                                vesting_escrow_account.key,
+                                token_mint_account.key,
+                                vesting_account.key,
+                            )?,
+                            &[
+                                vesting_escrow_account.clone(),
+                                token_mint_account.clone(),
+                                token_program.clone(),
+                            ],
+                        )?;
+
+                        VestingAccount::new(*user_account.key)
+                    } else {
+                        load_state::<VestingAccount>(&vesting_account.data.borrow())?
+                    };
+
+                    //pay out what already vested while we're here, earlier grants keep their schedule
+                    let releasable = vesting.releasable(current_time);
+                    if releasable > 0 {
+                        invoke_signed(
+                            &transfer(
+                                &TOKEN_PROGRAM_ID,
+                                vesting_escrow_account.key,
+                                user_token_account.key,
+                                vesting_account.key,
+                                &[],
+                                releasable,
+                            )?,
+                            &[
+                                vesting_escrow_account.clone(),
+                                user_token_account.clone(),
+                                vesting_account.clone(),
+                                token_program.clone(),
+                            ],
+                            &[&[
+                                vesting_seed,
+                                user_account.key.as_ref(),
+                                &[vesting_bump_seed],
+                            ]],
+                        )?;
+                        vesting.released += releasable;
+                        msg!("Released {} vested tokens", releasable);
+                    }
+
+                    vesting.add_grant(VestingGrant {
+                        amount: claim_amount,
+                        start: current_time,
+                        schedule,
+                    })?;
+                    vesting.serialize(&mut &mut vesting_account.data.borrow_mut()[..])?;
+
+                    msg!(
+                        "{} tokens vesting over {} seconds, {} grants still vesting",
+                        claim_amount,
+                        schedule.duration_seconds,
+                        vesting.grants.len()
+                    );
+                    vesting_escrow_account
+                }
+                None => user_token_account,
+            };
+
+            //creating token transfer instruction i.e. CPI
+            let transfer_instruction = transfer(
+                &TOKEN_PROGRAM_ID,
+                faucet_treasury_account.key, //source token account
+                destination_account.key,     //destination token account
+                &faucet_config_pda,          //authority (faucet config PDA)
+                &[],
+                claim_amount, //amount to transfer
+            )
+            .map_err(|e| {
+                msg!("Failed to create transfer instruction: {:?}", e);
+                ProgramError::InvalidInstructionData
+            })?;
+
+            //execute the token transfer via CPI with PDA signature
+            invoke_signed(
+                &transfer_instruction,
+                &[
+                    faucet_treasury_account.clone(),
+                    destination_account.clone(),
+                    faucet_authority_account.clone(),
+                    token_program.clone(),
+                ],
+                &[&[faucet_config_seed, &[faucet_bump_seed]]], //PDA signature
+            )?;
+
+            //basket mints ride along on the same claim and cooldown
+            for entry in &faucet_config.basket {
+                let basket_treasury_account = next_account_info(accounts_iter)?;
+                let basket_user_token_account = next_account_info(accounts_iter)?;
+
+                if *basket_treasury_account.key != entry.treasury {
+                    msg!(
+                        "Basket treasury for {} must be {}",
+                        entry.mint,
+                        entry.treasury
+                    );
+                    return Err(ProgramError::InvalidAccountData);
+                }
+
+                let basket_user_token_data =
+                    spl_token::state::Account::unpack(&basket_user_token_account.data.borrow())
+                        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+                if basket_user_token_data.mint != entry.mint
+                    || basket_user_token_data.owner != *user_account.key
+                {
+                    msg!(
+                        "User token account must hold {} and be owned by the user",
+                        entry.mint
+                    );
+                    return Err(ProgramError::InvalidAccountData);
+                }
+
+                let basket_treasury_data =
+                    spl_token::state::Account::unpack(&basket_treasury_account.data.borrow())
+                        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+                if basket_treasury_data.amount < entry.amount {
+                    msg!(
+                        "Basket treasury has {} of {}, but {} needed!",
+                        basket_treasury_data.amount,
+                        entry.mint,
+                        entry.amount
+                    );
+                    return Err(FaucetError::InsufficientFunds.into());
+                }
+
+                invoke_signed(
+                    &transfer(
+                        &TOKEN_PROGRAM_ID,
+                        basket_treasury_account.key,
+                        basket_user_token_account.key,
+                        &faucet_config_pda,
+                        &[],
+                        entry.amount,
+                    )?,
+                    &[
+                        basket_treasury_account.clone(),
+                        basket_user_token_account.clone(),
+                        faucet_authority_account.clone(),
+                        token_program.clone(),
+                    ],
+                    &[&[faucet_config_seed, &[faucet_bump_seed]]],
+                )?;
+
+                msg!("Basket: {} of {}", entry.amount, entry.mint);
+            }
+
+            //updating user's claim records
+            user_record.last_claim_time = current_time;
+            user_record.last_claim_slot = clock.slot;
+            user_record.total_claims += 1;
+
+            //saving the updated record
+            user_record.serialize(&mut &mut user_claim_record_account.data.borrow_mut()[..])?;
+
+            msg!("Tokens have been transferred successfully!");
+            msg!("User: {}", user_account.key);
+            msg!("Amount: {}", claim_amount);
+            msg!("Total user claims: {}", user_record.total_claims);
+
+            emit_event(&FaucetEvent::Claimed {
+                user: *user_account.key,
+                amount: claim_amount,
+                total_claims: user_record.total_claims,
+                lottery_tier,
+            })?;
+
+            //warn ops before users start hitting InsufficientFunds
+            check_low_water_mark(
+                &mut faucet_config,
+                faucet_account_config,
+                treasury_data.amount - claim_amount,
+            )?;
+        }
+
+        FaucetInstruction::UpdateFaucetConfig(update) => {
+            msg!("Processing faucet update request!");
+
+            let accounts_iter = &mut accounts.iter();
+
+            let admin_account = next_account_info(accounts_iter)?;
+            let faucet_config_account = next_account_info(accounts_iter)?;
+
+            // Validate admin is signer
+            if !admin_account.is_signer {
+                msg!("Admin must be the signer!");
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+
+            //any program account starting with the signer's key could pass the admin check below
+            let (faucet_config_pda, _faucet_bump_seed) =
+                Pubkey::find_program_address(&[b"faucet_config"], program_id);
+
+            if faucet_config_pda != *faucet_config_account.key
+                || faucet_config_account.owner != program_id
+            {
+                msg!("Faucet config account is not the correct PDA");
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            //loading the current config
+            let mut faucet_config =
+                load_state::<FaucetConfig>(&faucet_config_account.data.borrow())?;
+
+            //checking if the caller is the real admin
+            if admin_account.key != &faucet_config.admin {
+                msg!("Unauthorized admin access attempt!");
+                msg!("Expected admin: {}", faucet_config.admin);
+                msg!("Actual caller: {}", admin_account.key);
+                return Err(FaucetError::UnauthorizedAdmin.into());
+            }
+
+            msg!("Admin account verified!");
+
+            //a Resume clearing PAUSE_CONFIG_UPDATES unlocks this again
+            if faucet_config.is_paused(PAUSE_CONFIG_UPDATES) {
+                msg!("Config updates are paused");
+                return Err(FaucetError::OperationPaused.into());
+            }
+
+            if let Some(tokens) = update.tokens_per_claim {
+                faucet_config.tokens_per_claim = tokens;
+                msg!("Updated tokens per claim to: {}", tokens);
+            }
+
+            if let Some(unit) = update.cooldown_unit {
+                faucet_config.cooldown_unit = unit;
+                msg!("Updated cooldown unit to: {}", unit.name());
+            }
+
+            if let Some(cooldown) = update.cooldown_seconds {
+                faucet_config.cooldown_seconds = cooldown;
+                msg!(
+                    "Updated cooldown to: {} {}",
+                    cooldown,
+                    faucet_config.cooldown_unit.name()
+                );
+            }
+
+            if let Some(mode) = update.claim_mode {
+                faucet_config.claim_mode = mode;
+                msg!("Updated claim mode to: {:?}", mode);
+            }
+
+            if let Some(policy) = update.rate_limit {
+                policy.validate()?;
+                faucet_config.rate_limit = policy;
+                msg!("Updated rate limit policy to: {:?}", policy);
+            }
+
+            if let Some(starts_at) = update.starts_at {
+                faucet_config.starts_at = starts_at;
+                msg!("Updated start time to: {:?}", starts_at);
+            }
+
+            if let Some(ends_at) = update.ends_at {
+                faucet_config.ends_at = ends_at;
+                msg!("Updated end time to: {:?}", ends_at);
+            }
+
+            if let (Some(starts_at), Some(ends_at)) =
+                (faucet_config.starts_at, faucet_config.ends_at)
+                && starts_at >= ends_at
+            {
+                msg!("Faucet must start before it ends");
+                return Err(FaucetError::InvalidConfig.into());
+            }
+
+            if let Some(emission) = update.emission {
+                emission.validate()?;
+                faucet_config.emission = emission;
+                msg!("Updated emission curve to: {:?}", emission);
+            }
+
+            if let Some(low_water_mark) = update.low_water_mark {
+                faucet_config.low_water_mark = low_water_mark;
+                msg!("Updated low water mark to: {:?}", low_water_mark);
+            }
+
+            if let Some(pause) = update.pause_on_low_water {
+                faucet_config.pause_on_low_water = pause;
+                msg!("Updated pause on low water to: {}", pause);
+            }
+
+            if let Some(voucher_authority) = update.voucher_authority {
+                faucet_config.voucher_authority = voucher_authority;
+                msg!("Updated voucher authority to: {:?}", voucher_authority);
+            }
+
+            if let Some(anti_bot) = update.anti_bot {
+                faucet_config.anti_bot = anti_bot;
+                msg!("Updated anti bot checks to: {}", anti_bot);
+            }
+
+            if let Some(token_gate) = update.token_gate {
+                faucet_config.token_gate = token_gate;
+                msg!("Updated token gate to: {:?}", token_gate);
+            }
+
+            if let Some(max_balance) = update.max_recipient_token_balance {
+                faucet_config.max_recipient_token_balance = max_balance;
+                msg!("Updated max recipient token balance to: {:?}", max_balance);
+            }
+
+            if let Some(max_lamports) = update.max_recipient_lamports {
+                faucet_config.max_recipient_lamports = max_lamports;
+                msg!("Updated max recipient lamports to: {:?}", max_lamports);
+            }
+
+            if let Some(fee) = update.claim_fee_lamports {
+                //the fee vault is a plain system account, it can't hold less than rent exemption
+                let minimum_fee = Rent::get()?.minimum_balance(0);
+                if fee != 0 && fee < minimum_fee {
+                    msg!("Claim fee must be 0 or at least {} lamports", minimum_fee);
+                    return Err(FaucetError::InvalidConfig.into());
+                }
+                faucet_config.claim_fee_lamports = fee;
+                msg!("Updated claim fee to: {} lamports", fee);
+            }
+
+            if let Some(treasurer) = update.treasurer {
+                faucet_config.treasurer = treasurer;
+                msg!("Updated treasurer to: {:?}", treasurer);
+            }
+
+            if let Some(bond) = update.claim_bond_lamports {
+                //slashed bonds land in the fee vault, which has the same rent floor as fees
+                let minimum_bond = Rent::get()?.minimum_balance(0);
+                if bond != 0 && bond < minimum_bond {
+                    msg!("Claim bond must be 0 or at least {} lamports", minimum_bond);
+                    return Err(FaucetError::InvalidConfig.into());
+                }
+                faucet_config.claim_bond_lamports = bond;
+                msg!("Updated claim bond to: {} lamports", bond);
+            }
+
+            if let Some(holding) = update.bond_holding_seconds {
+                if holding < 0 {
+                    msg!("Bond holding period can't be negative");
+                    return Err(FaucetError::InvalidConfig.into());
+                }
+                faucet_config.bond_holding_seconds = holding;
+                msg!("Updated bond holding period to: {} seconds", holding);
+            }
+
+            if let Some(streak) = update.streak {
+                if let Some(policy) = &streak {
+                    policy.validate()?;
+                }
+                msg!("Updated claim streaks to: {:?}", streak);
+                faucet_config.streak = streak;
+            }
+
+            if let Some(bonus) = update.referral_bonus {
+                faucet_config.referral_bonus = bonus;
+                msg!("Updated referral bonus to: {}", bonus);
+            }
+
+            if let Some(vesting) = update.vesting {
+                if let Some(schedule) = vesting {
+                    schedule.validate()?;
+                }
+                faucet_config.vesting = vesting;
+                msg!("Updated vesting to: {:?}", vesting);
+            }
+
+            if let Some(basket) = update.basket {
+                let duplicate_mint = basket.iter().enumerate().any(|(i, entry)| {
+                    entry.mint == faucet_config.token_mint
+                        || basket[..i].iter().any(|other| other.mint == entry.mint)
+                });
+
+                if basket.len() > MAX_BASKET_ENTRIES
+                    || duplicate_mint
+                    || basket.iter().any(|entry| entry.amount == 0)
+                {
+                    msg!(
+                        "Basket takes up to {} distinct extra mints with non zero amounts",
+                        MAX_BASKET_ENTRIES
+                    );
+                    return Err(FaucetError::InvalidConfig.into());
+                }
+
+                msg!("Updated basket to {} extra mints", basket.len());
+                faucet_config.basket = basket;
+            }
+
+            if let Some(lottery) = update.lottery {
+                if let Some(draws) = &lottery {
+                    draws.validate()?;
+                }
+                msg!("Updated lottery to: {:?}", lottery);
+                faucet_config.lottery = lottery;
+            }
+
+            //checked against the final settings, so later updates can't undo it either
+            if let Some(lottery) = &faucet_config.lottery {
+                let max_claim = faucet_config
+                    .rate_limit
+                    .max_claim(faucet_config.tokens_per_claim);
+
+                if !faucet_config.anti_bot {
+                    msg!("Lottery claims need anti bot checks on");
+                    return Err(FaucetError::InvalidConfig.into());
+                }
+
+                if !lottery.fits(max_claim) {
+                    msg!(
+                        "Lottery tiers must fit in a single claim of at most {} tokens",
+                        max_claim
+                    );
+                    return Err(FaucetError::InvalidConfig.into());
+                }
+            }
+
+            faucet_config.serialize(&mut &mut faucet_config_account.data.borrow_mut()[..])?;
+
+            msg!("Faucet Configuration updated successfully!")
+        }
+
+        FaucetInstruction::EmergencyPause {
+            flags,
+            reason,
+            memo,
+        } => {
+            msg!("Emergency Pause has been requested!");
+
+            let accounts_iter = &mut accounts.iter();
+
+            let admin_account = next_account_info(accounts_iter)?;
+            let faucet_config_account = next_account_info(accounts_iter)?;
+            let pause_log_account = next_account_info(accounts_iter)?;
+            let system_program = next_account_info(accounts_iter)?;
+
+            //validating admin signature
+            if !admin_account.is_signer {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+
+            //a forged config would let anyone name themselves admin
+            let (faucet_config_pda, _faucet_bump_seed) =
+                Pubkey::find_program_address(&[b"faucet_config"], program_id);
+
+            if faucet_config_pda != *faucet_config_account.key
+                || faucet_config_account.owner != program_id
+            {
+                msg!("Faucet config account is not the correct PDA");
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            //loading config and verifying the admin
+            let mut faucet_config =
+                load_state::<FaucetConfig>(&faucet_config_account.data.borrow())?;
+
+            if admin_account.key != &faucet_config.admin {
+                msg!(
+                    "Unauthorized attempt to pause the faucet from: {}",
+                    admin_account.key
+                );
+
+                return Err(FaucetError::UnauthorizedAdmin.into());
+            }
+
+            if flags == 0 || flags & !PAUSE_ALL != 0 {
+                msg!("Invalid pause flags: {:#010b}", flags);
+                return Err(FaucetError::InvalidConfig.into());
+            }
+
+            faucet_config.paused |= flags;
+            faucet_config.serialize(&mut &mut faucet_config_account.data.borrow_mut()[..])?;
+
+            append_pause_log(
+                program_id,
+                admin_account,
+                pause_log_account,
+                system_program,
+                PauseLogEntry {
+                    action: PauseAction::Pause,
+                    flags,
+                    reason,
+                    memo: pack_memo(memo.as_deref())?,
+                    admin: *admin_account.key,
+                    timestamp: Clock::get()?.unix_timestamp,
+                },
+            )?;
+
+            emit_event(&FaucetEvent::Paused {
+                admin: *admin_account.key,
+                flags,
+                reason,
+            })?;
+
+            msg!("Faucet has been pause by the admin!");
+            msg!("Admin: {}", admin_account.key);
+            msg!("Reason code: {}", reason);
+            msg!("All tokens have been blocked until the Faucet resumes!");
+        }
+
+        FaucetInstruction::WithdrawTreasury { amount } => {
+            msg!("Processing treasury withdrawal of: {} tokens", amount);
+
+            let accounts_iter = &mut accounts.iter();
+
+            let admin_account = next_account_info(accounts_iter)?;
+            let faucet_config_account = next_account_info(accounts_iter)?;
+            let faucet_authority_account = next_account_info(accounts_iter)?; // PDA for signing
+            let faucet_treasury_account = next_account_info(accounts_iter)?; // Source
+            let admin_token_account = next_account_info(accounts_iter)?; // Destination
+            let token_program = next_account_info(accounts_iter)?;
+
+            //security checks
+            if !admin_account.is_signer {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+
+            let faucet_config_seed = b"faucet_config";
+            let (faucet_config_pda, faucet_bump_seed) =
+                Pubkey::find_program_address(&[faucet_config_seed], program_id);
+
+            if faucet_config_pda != *faucet_config_account.key
+                || faucet_config_account.owner != program_id
+            {
+                msg!("Faucet config account is not the correct PDA");
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            let faucet_config = load_state::<FaucetConfig>(&faucet_config_account.data.borrow())?;
+            faucet_config.check_treasury(faucet_treasury_account)?;
+
+            if admin_account.key != &faucet_config.admin {
+                return Err(FaucetError::UnauthorizedAdmin.into());
+            }
+
+            if faucet_config.is_paused(PAUSE_WITHDRAWALS) {
+                msg!("Treasury withdrawals are paused");
+                return Err(FaucetError::OperationPaused.into());
+            }
+
+            // Check treasury has enough tokens
+            let treasury_data =
+                spl_token::state::Account::unpack(&faucet_treasury_account.data.borrow())
+                    .map_err(|_| FaucetError::InsufficientFunds)?;
+
+            if treasury_data.amount < amount {
+                msg!(
+                    "Cannot withdraw {} tokens, treasury only has {}",
+                    amount,
+                    treasury_data.amount
+                );
+                return Err(FaucetError::InsufficientFunds.into());
+            }
+
+            // Create withdrawal transfer
+            let transfer_instruction = spl_token::instruction::transfer(
+                &spl_token::id(),
+                faucet_treasury_account.key, // From treasury
+                admin_token_account.key,     // To admin
+                &faucet_config_pda,          // Authority (PDA)
+                &[],
+                amount,
+            )?;
+
+            invoke_signed(
+                &transfer_instruction,
+                &[
+                    faucet_treasury_account.clone(),
+                    admin_token_account.clone(),
+                    faucet_authority_account.clone(),
+                    token_program.clone(),
+                ],
+                &[&[faucet_config_seed, &[faucet_bump_seed]]],
+            )?;
+
+            msg!("✅ Treasury withdrawal successful!");
+            msg!("Amount: {} tokens", amount);
+            msg!("Withdrawn to: {}", admin_token_account.key);
+        }
+
+        FaucetInstruction::Deposit { amount } => {
+            msg!("Processing deposit of: {} tokens", amount);
+
+            let accounts_iter = &mut accounts.iter();
+
+            let donor_account = next_account_info(accounts_iter)?;
+            let donor_contribution_account = next_account_info(accounts_iter)?;
+            let donor_token_account = next_account_info(accounts_iter)?; // Source
+            let faucet_treasury_account = next_account_info(accounts_iter)?; // Destination
+            let faucet_config_account = next_account_info(accounts_iter)?;
+            let token_program = next_account_info(accounts_iter)?;
+            let system_program = next_account_info(accounts_iter)?;
+
+            if !donor_account.is_signer {
+                msg!("Donor account must be a signer");
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+
+            if amount == 0 {
+                msg!("Deposit amount must be greater than zero");
+                return Err(FaucetError::InvalidAmount.into());
+            }
+
+            //anyone can deposit, so make sure the config and treasury are the real ones
+            let faucet_config_seed = b"faucet_config";
+            let (faucet_config_pda, _faucet_bump_seed) =
+                Pubkey::find_program_address(&[faucet_config_seed], program_id);
+
+            if faucet_config_pda != *faucet_config_account.key
+                || faucet_config_account.owner != program_id
+            {
+                msg!("Faucet config account is not the correct PDA");
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            let mut faucet_config =
+                load_state::<FaucetConfig>(&faucet_config_account.data.borrow())?;
+
+            if faucet_config.is_paused(PAUSE_DEPOSITS) {
+                msg!("Deposits are paused");
+                return Err(FaucetError::OperationPaused.into());
+            }
+
+            //only the pinned treasury pays out, tokens sent anywhere else would be stuck
+            faucet_config.check_treasury(faucet_treasury_account)?;
+
+            //PDA for donor contribution
+            let donor_seed = b"donor";
+            let (donor_pda, donor_bump_seed) =
+                Pubkey::find_program_address(&[donor_seed, donor_account.key.as_ref()], program_id);
+
+            if donor_pda != *donor_contribution_account.key {
+                msg!("Donor contribution account is not the correct PDA");
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            let clock = Clock::get()?;
+
+            let mut contribution = if donor_contribution_account.data_len() == 0 {
+                msg!("First deposit from this donor... Creating a contribution record!");
+
+                let contribution = DonorContribution {
+                    donor: *donor_account.key,
+                    total_deposited: 0,
+                    deposit_count: 0,
+                    last_deposit_time: 0,
+                };
+
+                let required_space = borsh::to_vec(&contribution)?.len();
+                let rent = Rent::get()?;
+                let required_lamports = rent.minimum_balance(required_space);
+
+                let create_account_instruction = system_instruction::create_account(
+                    donor_account.key,
+                    donor_contribution_account.key,
+                    required_lamports,
+                    required_space as u64,
+                    program_id,
+                );
+
+                invoke_signed(
+                    &create_account_instruction,
+                    &[
+                        donor_account.clone(),
+                        donor_contribution_account.clone(),
+                        system_program.clone(),
+                    ],
+                    &[&[donor_seed, donor_account.key.as_ref(), &[donor_bump_seed]]],
+                )?;
+
+                faucet_config.total_donors += 1;
+                contribution
+            } else {
+                DonorContribution::try_from_slice(&donor_contribution_account.data.borrow())?
+            };
+
+            //donor signs the transfer, no PDA signature needed
+            let transfer_instruction = transfer(
+                &TOKEN_PROGRAM_ID,
+                donor_token_account.key,
+                faucet_treasury_account.key,
+                donor_account.key,
+                &[],
+                amount,
+            )?;
+
+            invoke(
+                &transfer_instruction,
+                &[
+                    donor_token_account.clone(),
+                    faucet_treasury_account.clone(),
+                    donor_account.clone(),
+                    token_program.clone(),
+                ],
+            )?;
+
+            contribution.total_deposited = contribution
+                .total_deposited
+                .checked_add(amount)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+            contribution.deposit_count += 1;
+            contribution.last_deposit_time = clock.unix_timestamp;
+            contribution.serialize(&mut &mut donor_contribution_account.data.borrow_mut()[..])?;
+
+            faucet_config.total_deposited = faucet_config
+                .total_deposited
+                .checked_add(amount)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+            faucet_config.serialize(&mut &mut faucet_config_account.data.borrow_mut()[..])?;
+
+            emit_event(&FaucetEvent::Deposited {
+                donor: *donor_account.key,
+                amount,
+                donor_total: contribution.total_deposited,
+                faucet_total: faucet_config.total_deposited,
+            })?;
+
+            msg!("Deposit successful!");
+            msg!("Donor: {}", donor_account.key);
+            msg!("Donor total: {} tokens", contribution.total_deposited);
+            msg!(
+                "Faucet total deposits: {} tokens",
+                faucet_config.total_deposited
+            );
+        }
+
+        FaucetInstruction::CloseExpiredFaucet => {
+            msg!("Checking if the faucet has expired");
+
+            let accounts_iter = &mut accounts.iter();
+
+            let faucet_config_account = next_account_info(accounts_iter)?;
+
+            let faucet_config_seed = b"faucet_config";
+            let (faucet_config_pda, _faucet_bump_seed) =
+                Pubkey::find_program_address(&[faucet_config_seed], program_id);
+
+            if faucet_config_pda != *faucet_config_account.key
+                || faucet_config_account.owner != program_id
+            {
+                msg!("Faucet config account is not the correct PDA");
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            let mut faucet_config =
+                load_state::<FaucetConfig>(&faucet_config_account.data.borrow())?;
+
+            let current_time = Clock::get()?.unix_timestamp;
+
+            let Some(ends_at) = faucet_config.ends_at else {
+                msg!("Faucet has no end time");
+                return Err(FaucetError::FaucetNotEnded.into());
+            };
+
+            if !faucet_config.has_ended(current_time) {
+                msg!(
+                    "Faucet is open for another {} seconds",
+                    ends_at - current_time
+                );
+                return Err(FaucetError::FaucetNotEnded.into());
+            }
+
+            if faucet_config.is_paused(PAUSE_CLAIMS) {
+                msg!("Faucet is already inactive");
+                return Ok(());
+            }
+
+            faucet_config.paused |= PAUSE_CLAIMS;
+            faucet_config.serialize(&mut &mut faucet_config_account.data.borrow_mut()[..])?;
+
+            emit_event(&FaucetEvent::FaucetExpired {
+                ends_at,
+                closed_at: current_time,
+            })?;
+
+            msg!("Faucet expired and has been deactivated!");
+        }
+
+        FaucetInstruction::Resume {
+            flags,
+            reason,
+            memo,
+        } => {
+            msg!("Resume has been requested!");
+
+            let accounts_iter = &mut accounts.iter();
+
+            let admin_account = next_account_info(accounts_iter)?;
+            let faucet_config_account = next_account_info(accounts_iter)?;
+            let pause_log_account = next_account_info(accounts_iter)?;
+            let system_program = next_account_info(accounts_iter)?;
+
+            if !admin_account.is_signer {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+
+            //a forged config would let anyone name themselves admin
+            let (faucet_config_pda, _faucet_bump_seed) =
+                Pubkey::find_program_address(&[b"faucet_config"], program_id);
+
+            if faucet_config_pda != *faucet_config_account.key
+                || faucet_config_account.owner != program_id
+            {
+                msg!("Faucet config account is not the correct PDA");
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            let mut faucet_config =
+                load_state::<FaucetConfig>(&faucet_config_account.data.borrow())?;
+
+            if admin_account.key != &faucet_config.admin {
+                msg!(
+                    "Unauthorized attempt to resume the faucet from: {}",
+                    admin_account.key
+                );
+                return Err(FaucetError::UnauthorizedAdmin.into());
+            }
+
+            if flags == 0 || flags & !PAUSE_ALL != 0 {
+                msg!("Invalid resume flags: {:#010b}", flags);
+                return Err(FaucetError::InvalidConfig.into());
+            }
+
+            faucet_config.paused &= !flags;
+            faucet_config.serialize(&mut &mut faucet_config_account.data.borrow_mut()[..])?;
+
+            append_pause_log(
+                program_id,
+                admin_account,
+                pause_log_account,
+                system_program,
+                PauseLogEntry {
+                    action: PauseAction::Resume,
+                    flags,
+                    reason,
+                    memo: pack_memo(memo.as_deref())?,
+                    admin: *admin_account.key,
+                    timestamp: Clock::get()?.unix_timestamp,
+                },
+            )?;
+
+            emit_event(&FaucetEvent::Resumed {
+                admin: *admin_account.key,
+                flags,
+                reason,
+            })?;
+
+            msg!("Faucet has been resumed by the admin!");
+            msg!("Still paused: {:#010b}", faucet_config.paused);
+            msg!("Reason code: {}", reason);
+        }
+
+        FaucetInstruction::GrantAllowance {
+            distributor,
+            amount,
+            expires_at,
+        } => {
+            msg!("Granting {} tokens to distributor {}", amount, distributor);
+
+            let accounts_iter = &mut accounts.iter();
+
+            let admin_account = next_account_info(accounts_iter)?;
+            let faucet_config_account = next_account_info(accounts_iter)?;
+            let distributor_allowance_account = next_account_info(accounts_iter)?;
+            let system_program = next_account_info(accounts_iter)?;
+
+            if !admin_account.is_signer {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+
+            //only the real config's admin can hand out allowances
+            let (faucet_config_pda, _faucet_bump_seed) =
+                Pubkey::find_program_address(&[b"faucet_config"], program_id);
+
+            if faucet_config_pda != *faucet_config_account.key
+                || faucet_config_account.owner != program_id
+            {
+                msg!("Faucet config account is not the correct PDA");
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            let faucet_config = load_state::<FaucetConfig>(&faucet_config_account.data.borrow())?;
+
+            if admin_account.key != &faucet_config.admin {
+                return Err(FaucetError::UnauthorizedAdmin.into());
+            }
+
+            let distributor_seed = b"distributor";
+            let (distributor_pda, distributor_bump_seed) =
+                Pubkey::find_program_address(&[distributor_seed, distributor.as_ref()], program_id);
+
+            if distributor_pda != *distributor_allowance_account.key {
+                msg!("Distributor allowance account is not the correct PDA");
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            let mut allowance = if distributor_allowance_account.data_len() == 0 {
+                let required_space = DistributorAllowance::SPACE;
+                let required_lamports = Rent::get()?.minimum_balance(required_space);
+
+                let create_account_instruction = system_instruction::create_account(
+                    admin_account.key,
+                    distributor_allowance_account.key,
+                    required_lamports,
+                    required_space as u64,
+                    program_id,
+                );
+
+                invoke_signed(
+                    &create_account_instruction,
+                    &[
+                        admin_account.clone(),
+                        distributor_allowance_account.clone(),
+                        system_program.clone(),
+                    ],
+                    &[&[
+                        distributor_seed,
+                        distributor.as_ref(),
+                        &[distributor_bump_seed],
+                    ]],
+                )?;
+
+                DistributorAllowance {
+                    distributor,
+                    remaining: 0,
+                    total_distributed: 0,
+                    expires_at: None,
+                }
+            } else {
+                load_state::<DistributorAllowance>(&distributor_allowance_account.data.borrow())?
+            };
+
+            allowance.remaining = amount;
+            allowance.expires_at = expires_at;
+            allowance.serialize(&mut &mut distributor_allowance_account.data.borrow_mut()[..])?;
+
+            emit_event(&FaucetEvent::AllowanceGranted {
+                distributor,
+                amount,
+                expires_at,
+            })?;
+
+            msg!("Allowance granted!");
+            msg!("Expires at: {:?}", expires_at);
+        }
+
+        FaucetInstruction::Distribute { recipient, amount } => {
+            msg!("Distributing {} tokens to {}", amount, recipient);
+
+            let accounts_iter = &mut accounts.iter();
+
+            let distributor_account = next_account_info(accounts_iter)?;
+            let distributor_allowance_account = next_account_info(accounts_iter)?;
+            let recipient_token_account = next_account_info(accounts_iter)?;
+            let faucet_treasury_account = next_account_info(accounts_iter)?;
+            let faucet_config_account = next_account_info(accounts_iter)?;
+            let token_program = next_account_info(accounts_iter)?;
+
+            if !distributor_account.is_signer {
+                msg!("Distributor must be a signer");
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+
+            if amount == 0 {
+                return Err(FaucetError::InvalidAmount.into());
+            }
+
+            let faucet_config_seed = b"faucet_config";
+            let (faucet_config_pda, faucet_bump_seed) =
+                Pubkey::find_program_address(&[faucet_config_seed], program_id);
+
+            if faucet_config_pda != *faucet_config_account.key
+                || faucet_config_account.owner != program_id
+            {
+                msg!("Faucet config account is not the correct PDA");
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            let faucet_config = load_state::<FaucetConfig>(&faucet_config_account.data.borrow())?;
+            faucet_config.check_treasury(faucet_treasury_account)?;
+
+            //distributions hand tokens to users just like claims do
+            if faucet_config.is_paused(PAUSE_CLAIMS) {
+                msg!("Faucet is currently inactive");
+                return Err(FaucetError::FaucetInactive.into());
+            }
+
+            let (distributor_pda, _distributor_bump_seed) = Pubkey::find_program_address(
+                &[b"distributor", distributor_account.key.as_ref()],
+                program_id,
+            );
+
+            if distributor_pda != *distributor_allowance_account.key
+                || distributor_allowance_account.owner != program_id
+            {
+                msg!("Distributor allowance account is not the correct PDA");
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            let mut allowance =
+                load_state::<DistributorAllowance>(&distributor_allowance_account.data.borrow())?;
+
+            let current_time = Clock::get()?.unix_timestamp;
+            if allowance
+                .expires_at
+                .is_some_and(|expires_at| current_time >= expires_at)
+            {
+                msg!(
+                    "Distributor allowance expired at {:?}",
+                    allowance.expires_at
+                );
+                return Err(FaucetError::AllowanceExpired.into());
+            }
+
+            if amount > allowance.remaining {
+                msg!(
+                    "Distributor has {} tokens left, {} requested",
+                    allowance.remaining,
+                    amount
+                );
+                return Err(FaucetError::AllowanceExceeded.into());
+            }
+
+            let recipient_token_data =
+                spl_token::state::Account::unpack(&recipient_token_account.data.borrow())
+                    .map_err(|_| ProgramError::InvalidAccountData)?;
+
+            if recipient_token_data.mint != faucet_config.token_mint
+                || recipient_token_data.owner != recipient
+            {
+                msg!(
+                    "Recipient token account must hold the faucet mint and be owned by the recipient"
+                );
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            let treasury_data =
+                spl_token::state::Account::unpack(&faucet_treasury_account.data.borrow())
+                    .map_err(|_| ProgramError::InvalidAccountData)?;
+
+            if treasury_data.amount < amount {
+                msg!(
+                    "Treasury has {} tokens, but {} tokens requested!",
+                    treasury_data.amount,
+                    amount
+                );
+                return Err(FaucetError::InsufficientFunds.into());
+            }
+
+            let transfer_instruction = transfer(
+                &TOKEN_PROGRAM_ID,
+                faucet_treasury_account.key,
+                recipient_token_account.key,
+                &faucet_config_pda,
+                &[],
+                amount,
+            )?;
+
+            invoke_signed(
+                &transfer_instruction,
+                &[
+                    faucet_treasury_account.clone(),
+                    recipient_token_account.clone(),
+                    faucet_config_account.clone(),
+                    token_program.clone(),
+                ],
+                &[&[faucet_config_seed, &[faucet_bump_seed]]],
+            )?;
+
+            allowance.remaining -= amount;
+            allowance.total_distributed = allowance.total_distributed.saturating_add(amount);
+            allowance.serialize(&mut &mut distributor_allowance_account.data.borrow_mut()[..])?;
+
+            emit_event(&FaucetEvent::Distributed {
+                distributor: *distributor_account.key,
+                recipient,
+                amount,
+                remaining: allowance.remaining,
+            })?;
+
+            msg!("Distribution successful!");
+            msg!("Remaining allowance: {}", allowance.remaining);
+        }
+
+        FaucetInstruction::Airdrop { amounts } => {
+            msg!("Processing airdrop to {} recipients", amounts.len());
+
+            let accounts_iter = &mut accounts.iter();
+
+            let admin_account = next_account_info(accounts_iter)?;
+            let faucet_config_account = next_account_info(accounts_iter)?;
+            let faucet_treasury_account = next_account_info(accounts_iter)?;
+            let token_program = next_account_info(accounts_iter)?;
+            let recipient_accounts = accounts_iter.as_slice();
+
+            if !admin_account.is_signer {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+
+            let faucet_config_seed = b"faucet_config";
+            let (faucet_config_pda, faucet_bump_seed) =
+                Pubkey::find_program_address(&[faucet_config_seed], program_id);
+
+            if faucet_config_pda != *faucet_config_account.key
+                || faucet_config_account.owner != program_id
+            {
+                msg!("Faucet config account is not the correct PDA");
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            let mut faucet_config =
+                load_state::<FaucetConfig>(&faucet_config_account.data.borrow())?;
+            faucet_config.check_treasury(faucet_treasury_account)?;
+
+            if admin_account.key != &faucet_config.admin {
+                return Err(FaucetError::UnauthorizedAdmin.into());
+            }
+
+            if faucet_config.is_paused(PAUSE_WITHDRAWALS) {
+                msg!("Treasury withdrawals are paused");
+                return Err(FaucetError::OperationPaused.into());
+            }
+
+            if amounts.len() > MAX_AIRDROP_RECIPIENTS {
+                msg!(
+                    "At most {} recipients per airdrop, split the list",
+                    MAX_AIRDROP_RECIPIENTS
+                );
+                return Err(FaucetError::AirdropTooLarge.into());
+            }
+
+            if amounts.len() != recipient_accounts.len() {
+                msg!(
+                    "{} amounts given for {} recipient accounts",
+                    amounts.len(),
+                    recipient_accounts.len()
+                );
+                return Err(ProgramError::NotEnoughAccountKeys);
+            }
+
+            let mut treasury_balance =
+                spl_token::state::Account::unpack(&faucet_treasury_account.data.borrow())
+                    .map_err(|_| ProgramError::InvalidAccountData)?
+                    .amount;
+
+            let mut sent = 0u8;
+            let mut skipped = vec![];
+            let mut total_amount = 0u64;
+
+            for (index, (recipient_account, &amount)) in
+                recipient_accounts.iter().zip(amounts.iter()).enumerate()
+            {
+                //stop instead of failing so earlier transfers still land
+                if treasury_balance < amount {
+                    msg!(
+                        "Treasury ran dry at recipient {}, {} tokens left",
+                        index,
+                        treasury_balance
+                    );
+                    skipped.extend(index as u8..amounts.len() as u8);
+                    break;
+                }
+
+                //frozen or foreign accounts would fail the transfer and sink the whole batch
+                let receivable = *recipient_account.owner == TOKEN_PROGRAM_ID
+                    && spl_token::state::Account::unpack(&recipient_account.data.borrow())
+                        .is_ok_and(|token_account| {
+                            token_account.mint == faucet_config.token_mint
+                                && token_account.state == AccountState::Initialized
+                        });
+
+                if amount == 0 || !receivable {
+                    msg!("Skipping recipient {}: {}", index, recipient_account.key);
+                    skipped.push(index as u8);
+                    continue;
+                }
+
+                let transfer_instruction = transfer(
+                    &TOKEN_PROGRAM_ID,
+                    faucet_treasury_account.key,
+                    recipient_account.key,
+                    &faucet_config_pda,
+                    &[],
+                    amount,
+                )?;
+
+                invoke_signed(
+                    &transfer_instruction,
+                    &[
+                        faucet_treasury_account.clone(),
+                        recipient_account.clone(),
+                        faucet_config_account.clone(),
+                        token_program.clone(),
+                    ],
+                    &[&[faucet_config_seed, &[faucet_bump_seed]]],
+                )?;
+
+                treasury_balance -= amount;
+                total_amount += amount;
+                sent += 1;
+            }
+
+            faucet_config.total_airdropped =
+                faucet_config.total_airdropped.saturating_add(total_amount);
+            faucet_config.total_airdrop_recipients += sent as u64;
+            faucet_config.serialize(&mut &mut faucet_config_account.data.borrow_mut()[..])?;
+
+            msg!(
+                "Airdrop finished: {}/{} recipients paid, {} tokens",
+                sent,
+                amounts.len(),
+                total_amount
+            );
+
+            emit_event(&FaucetEvent::AirdropCompleted {
+                requested: amounts.len() as u8,
+                sent,
+                skipped,
+                total_amount,
+            })?;
+        }
+
+        FaucetInstruction::CloseClaimRecord => {
+            msg!("Processing claim record close");
+
+            let accounts_iter = &mut accounts.iter();
+
+            let user_account = next_account_info(accounts_iter)?;
+            let user_claim_record_account = next_account_info(accounts_iter)?;
+            let rent_receiver_account = next_account_info(accounts_iter)?;
+            let faucet_config_account = next_account_info(accounts_iter)?;
+
+            if !user_account.is_signer {
+                msg!("User account must be a signer");
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+
+            let (user_claim_pda, _user_bump_seed) = Pubkey::find_program_address(
+                &[b"user_claim", user_account.key.as_ref()],
+                program_id,
+            );
+
+            if user_claim_pda != *user_claim_record_account.key
+                || user_claim_record_account.owner != program_id
+            {
+                msg!("User claim record account is not the correct PDA");
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            let (faucet_config_pda, _faucet_bump_seed) =
+                Pubkey::find_program_address(&[b"faucet_config"], program_id);
+
+            if faucet_config_pda != *faucet_config_account.key {
+                msg!("Faucet config account is not the correct PDA");
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            let faucet_config = load_state::<FaucetConfig>(&faucet_config_account.data.borrow())?;
+            let mut user_record =
+                load_state::<UserClaimedRecord>(&user_claim_record_account.data.borrow())?;
+
+            if *rent_receiver_account.key != user_record.rent_receiver() {
+                msg!("Rent must go back to {}", user_record.rent_receiver());
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            //closing must not hand out a fresh cooldown early
+            let clock = Clock::get()?;
+            user_record.convert_rate_limit(faucet_config.cooldown_unit, &clock);
+            let idle = faucet_config.rate_limit.is_idle(
+                faucet_config.cooldown_seconds,
+                faucet_config.tokens_per_claim,
+                &user_record,
+                faucet_config.cooldown_unit.current_tick(&clock),
+            );
+
+            if !idle && !faucet_config.has_ended(clock.unix_timestamp) {
+                msg!("Claim record is still limiting this user, try again after the cooldown");
+                return Err(FaucetError::RecordInUse.into());
+            }
+
+            //the record holds the ban and the bond, neither may be dropped by closing
+            if user_record.banned || user_record.bonded_lamports > 0 {
+                msg!("Claim record holds a ban or an unreclaimed bond");
+                return Err(FaucetError::RecordInUse.into());
+            }
+
+            //a recreated record would count as a new referral
+            if faucet_config.referral_bonus > 0 && !faucet_config.has_ended(clock.unix_timestamp) {
+                msg!("Claim records stay open while referral bonuses are paid");
+                return Err(FaucetError::RecordInUse.into());
+            }
+
+            let lamports = user_claim_record_account.lamports();
+            **rent_receiver_account.try_borrow_mut_lamports()? = rent_receiver_account
+                .lamports()
+                .checked_add(lamports)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+            **user_claim_record_account.try_borrow_mut_lamports()? = 0;
+
+            user_claim_record_account.resize(0)?;
+            user_claim_record_account.assign(&system_program::ID);
+
+            msg!("Claim record closed!");
+            msg!(
+                "{} lamports returned to {}",
+                lamports,
+                rent_receiver_account.key
+            );
+        }
+
+        FaucetInstruction::ClaimWithVoucher {
+            amount,
+            nonce,
+            expiry,
+        } => {
+            msg!("Processing voucher claim for {} tokens", amount);
+
+            let accounts_iter = &mut accounts.iter();
+
+            let user_account = next_account_info(accounts_iter)?;
+            let used_voucher_account = next_account_info(accounts_iter)?;
+            let user_token_account = next_account_info(accounts_iter)?;
+            let faucet_treasury_account = next_account_info(accounts_iter)?;
+            let faucet_config_account = next_account_info(accounts_iter)?;
+            let token_program = next_account_info(accounts_iter)?;
+            let system_program = next_account_info(accounts_iter)?;
+            let instructions_account = next_account_info(accounts_iter)?;
+            let user_claim_record_account = next_account_info(accounts_iter)?;
+            let sponsor_account = next_optional_account(accounts_iter, |account| account.is_signer);
+            let rent_payer_account = sponsor_account.unwrap_or(user_account);
+
+            if !user_account.is_signer {
+                msg!("User account must be a signer");
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+
+            if amount == 0 {
+                return Err(FaucetError::InvalidAmount.into());
+            }
+
+            let faucet_config_seed = b"faucet_config";
+            let (faucet_config_pda, faucet_bump_seed) =
+                Pubkey::find_program_address(&[faucet_config_seed], program_id);
+
+            if faucet_config_pda != *faucet_config_account.key
+                || faucet_config_account.owner != program_id
+            {
+                msg!("Faucet config account is not the correct PDA");
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            let mut faucet_config =
+                load_state::<FaucetConfig>(&faucet_config_account.data.borrow())?;
+            faucet_config.check_treasury(faucet_treasury_account)?;
+
+            let clock = Clock::get()?;
+            let current_time = clock.unix_timestamp;
+
+            //a voucher doesn't get around a pause, the claim window or a ban
+            check_claim_eligibility(
+                program_id,
+                &faucet_config,
+                user_account.key,
+                user_claim_record_account,
+                Some(instructions_account),
+                current_time,
+            )?;
+
+            let Some(voucher_authority) = faucet_config.voucher_authority else {
+                msg!("Faucet does not accept vouchers");
+                return Err(FaucetError::InvalidVoucher.into());
+            };
+
+            if current_time >= expiry {
+                msg!("Voucher expired at {}", expiry);
+                return Err(FaucetError::VoucherExpired.into());
+            }
+
+            let voucher = ClaimVoucher {
+                faucet: faucet_config_pda,
+                user: *user_account.key,
+                amount,
+                nonce,
+                expiry,
+            };
+            verify_ed25519_signature(instructions_account, &voucher_authority, &voucher.message())?;
+
+            //the used voucher PDA existing means the nonce is spent
+            let nonce_bytes = nonce.to_le_bytes();
+            let (used_voucher_pda, used_voucher_bump_seed) = Pubkey::find_program_address(
+                &[b"voucher", user_account.key.as_ref(), &nonce_bytes],
+                program_id,
+            );
+
+            if used_voucher_pda != *used_voucher_account.key {
+                msg!("Used voucher account is not the correct PDA");
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            if used_voucher_account.data_len() != 0 {
+                msg!("Voucher nonce {} was already used", nonce);
+                return Err(FaucetError::VoucherAlreadyUsed.into());
+            }
+
+            let user_token_data =
+                spl_token::state::Account::unpack(&user_token_account.data.borrow())
+                    .map_err(|_| ProgramError::InvalidAccountData)?;
+
+            if user_token_data.mint != faucet_config.token_mint
+                || user_token_data.owner != *user_account.key
+            {
+                msg!("User token account must hold the faucet mint and be owned by the user");
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            let treasury_data =
+                spl_token::state::Account::unpack(&faucet_treasury_account.data.borrow())
+                    .map_err(|_| ProgramError::InvalidAccountData)?;
+
+            if treasury_data.amount < amount {
+                msg!(
+                    "Treasury has {} tokens, but {} tokens requested!",
+                    treasury_data.amount,
+                    amount
+                );
+                return Err(FaucetError::InsufficientFunds.into());
+            }
+
+            let rent = Rent::get()?;
+            invoke_signed(
+                &system_instruction::create_account(
+                    rent_payer_account.key,
+                    used_voucher_account.key,
+                    rent.minimum_balance(UsedVoucher::SPACE),
+                    UsedVoucher::SPACE as u64,
+                    program_id,
+                ),
+                &[
+                    rent_payer_account.clone(),
+                    used_voucher_account.clone(),
+                    system_program.clone(),
+                ],
+                &[&[
+                    b"voucher",
+                    user_account.key.as_ref(),
+                    &nonce_bytes,
+                    &[used_voucher_bump_seed],
+                ]],
+            )?;
+
+            UsedVoucher {
+                user: *user_account.key,
+                nonce,
+                amount,
+                used_at: current_time,
+            }
+            .serialize(&mut &mut used_voucher_account.data.borrow_mut()[..])?;
+
+            let transfer_instruction = transfer(
+                &TOKEN_PROGRAM_ID,
+                faucet_treasury_account.key,
+                user_token_account.key,
+                &faucet_config_pda,
+                &[],
+                amount,
+            )?;
+
+            invoke_signed(
+                &transfer_instruction,
+                &[
+                    faucet_treasury_account.clone(),
+                    user_token_account.clone(),
+                    faucet_config_account.clone(),
+                    token_program.clone(),
+                ],
+                &[&[faucet_config_seed, &[faucet_bump_seed]]],
+            )?;
+
+            msg!("Voucher claim successful!");
+            msg!("User: {}", user_account.key);
+            msg!("Amount: {}", amount);
+
+            check_low_water_mark(
+                &mut faucet_config,
+                faucet_config_account,
+                treasury_data.amount - amount,
+            )?;
+        }
+
+        FaucetInstruction::WithdrawFees => {
+            msg!("Processing fee withdrawal");
+
+            let accounts_iter = &mut accounts.iter();
+
+            let treasurer_account = next_account_info(accounts_iter)?;
+            let faucet_config_account = next_account_info(accounts_iter)?;
+            let fee_vault_account = next_account_info(accounts_iter)?;
+            let destination_account = next_account_info(accounts_iter)?;
+            let system_program = next_account_info(accounts_iter)?;
+
+            if !treasurer_account.is_signer {
+                msg!("Treasurer must be a signer");
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+
+            let (faucet_config_pda, _faucet_bump_seed) =
+                Pubkey::find_program_address(&[b"faucet_config"], program_id);
+
+            if faucet_config_pda != *faucet_config_account.key
+                || faucet_config_account.owner != program_id
+            {
+                msg!("Faucet config account is not the correct PDA");
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            let faucet_config = load_state::<FaucetConfig>(&faucet_config_account.data.borrow())?;
+
+            if *treasurer_account.key != faucet_config.treasurer() {
+                msg!("Only {} may withdraw fees", faucet_config.treasurer());
+                return Err(FaucetError::UnauthorizedTreasurer.into());
+            }
+
+            if faucet_config.is_paused(PAUSE_WITHDRAWALS) {
+                msg!("Withdrawals are paused");
+                return Err(FaucetError::OperationPaused.into());
+            }
+
+            let fee_vault_seed = b"fee_vault";
+            let (fee_vault_pda, fee_vault_bump_seed) =
+                Pubkey::find_program_address(&[fee_vault_seed], program_id);
+
+            if fee_vault_pda != *fee_vault_account.key {
+                msg!("Fee vault account is not the correct PDA");
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            let amount = fee_vault_account.lamports();
+            if amount == 0 {
+                msg!("No fees to withdraw");
+                return Err(FaucetError::InsufficientFunds.into());
+            }
+
+            invoke_signed(
+                &system_instruction::transfer(
+                    fee_vault_account.key,
+                    destination_account.key,
+                    amount,
+                ),
+                &[
+                    fee_vault_account.clone(),
+                    destination_account.clone(),
+                    system_program.clone(),
+                ],
+                &[&[fee_vault_seed, &[fee_vault_bump_seed]]],
+            )?;
+
+            emit_event(&FaucetEvent::FeesWithdrawn {
+                treasurer: *treasurer_account.key,
+                destination: *destination_account.key,
+                amount,
+            })?;
+
+            msg!(
+                "Withdrew {} lamports of fees to {}",
+                amount,
+                destination_account.key
+            );
+        }
+
+        FaucetInstruction::ReclaimBond => {
+            msg!("Processing bond reclaim");
+
+            let accounts_iter = &mut accounts.iter();
+
+            let user_account = next_account_info(accounts_iter)?;
+            let user_claim_record_account = next_account_info(accounts_iter)?;
+
+            if !user_account.is_signer {
+                msg!("User account must be a signer");
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+
+            let (user_claim_pda, _user_bump_seed) = Pubkey::find_program_address(
+                &[b"user_claim", user_account.key.as_ref()],
+                program_id,
+            );
+
+            if user_claim_pda != *user_claim_record_account.key
+                || user_claim_record_account.owner != program_id
+            {
+                msg!("User claim record account is not the correct PDA");
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            let mut user_record =
+                load_state::<UserClaimedRecord>(&user_claim_record_account.data.borrow())?;
+
+            if user_record.banned {
+                msg!("Bond was slashed, user is banned");
+                return Err(FaucetError::UserBanned.into());
+            }
+
+            if user_record.bonded_lamports == 0 {
+                msg!("No bond to reclaim");
+                return Err(FaucetError::InsufficientFunds.into());
+            }
+
+            let current_time = Clock::get()?.unix_timestamp;
+            if current_time < user_record.bond_unlocks_at {
+                msg!(
+                    "Bond unlocks in {} seconds",
+                    user_record.bond_unlocks_at - current_time
+                );
+                return Err(FaucetError::BondLocked.into());
+            }
+
+            let amount = user_record.bonded_lamports;
+            **user_claim_record_account.try_borrow_mut_lamports()? = user_claim_record_account
+                .lamports()
+                .checked_sub(amount)
+                .ok_or(ProgramError::InsufficientFunds)?;
+            **user_account.try_borrow_mut_lamports()? = user_account
+                .lamports()
+                .checked_add(amount)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+
+            user_record.bonded_lamports = 0;
+            user_record.serialize(&mut &mut user_claim_record_account.data.borrow_mut()[..])?;
+
+            msg!(
+                "Returned {} lamports of bond to {}",
+                amount,
+                user_account.key
+            );
+        }
+
+        FaucetInstruction::SlashBond { user } => {
+            msg!("Slashing bond of {}", user);
+
+            let accounts_iter = &mut accounts.iter();
+
+            let admin_account = next_account_info(accounts_iter)?;
+            let faucet_config_account = next_account_info(accounts_iter)?;
+            let user_claim_record_account = next_account_info(accounts_iter)?;
+            let fee_vault_account = next_account_info(accounts_iter)?;
+
+            if !admin_account.is_signer {
+                msg!("Admin must be the signer!");
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+
+            let (faucet_config_pda, _faucet_bump_seed) =
+                Pubkey::find_program_address(&[b"faucet_config"], program_id);
+
+            if faucet_config_pda != *faucet_config_account.key
+                || faucet_config_account.owner != program_id
+            {
+                msg!("Faucet config account is not the correct PDA");
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            let faucet_config = load_state::<FaucetConfig>(&faucet_config_account.data.borrow())?;
+
+            if admin_account.key != &faucet_config.admin {
+                msg!("Unauthorized admin access attempt!");
+                return Err(FaucetError::UnauthorizedAdmin.into());
+            }
+
+            let (user_claim_pda, _user_bump_seed) =
+                Pubkey::find_program_address(&[b"user_claim", user.as_ref()], program_id);
+
+            if user_claim_pda != *user_claim_record_account.key
+                || user_claim_record_account.owner != program_id
+            {
+                msg!("User claim record account is not the correct PDA");
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            let (fee_vault_pda, _fee_vault_bump_seed) =
+                Pubkey::find_program_address(&[b"fee_vault"], program_id);
+
+            if fee_vault_pda != *fee_vault_account.key {
+                msg!("Fee vault account is not the correct PDA");
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            let mut user_record =
+                load_state::<UserClaimedRecord>(&user_claim_record_account.data.borrow())?;
+
+            let amount = user_record.bonded_lamports;
+            **user_claim_record_account.try_borrow_mut_lamports()? = user_claim_record_account
+                .lamports()
+                .checked_sub(amount)
+                .ok_or(ProgramError::InsufficientFunds)?;
+            **fee_vault_account.try_borrow_mut_lamports()? = fee_vault_account
+                .lamports()
+                .checked_add(amount)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+
+            user_record.bonded_lamports = 0;
+            user_record.banned = true;
+            user_record.serialize(&mut &mut user_claim_record_account.data.borrow_mut()[..])?;
+
+            emit_event(&FaucetEvent::BondSlashed { user, amount })?;
+
+            msg!("Slashed {} lamports, {} is banned", amount, user);
+        }
+
+        FaucetInstruction::ReturnTokens { amount } => {
+            msg!("Processing return of {} tokens", amount);
+
+            let accounts_iter = &mut accounts.iter();
+
+            let user_account = next_account_info(accounts_iter)?;
+            let user_claim_record_account = next_account_info(accounts_iter)?;
+            let user_token_account = next_account_info(accounts_iter)?;
+            let faucet_treasury_account = next_account_info(accounts_iter)?;
+            let faucet_config_account = next_account_info(accounts_iter)?;
+            let token_program = next_account_info(accounts_iter)?;
+
+            if !user_account.is_signer {
+                msg!("User account must be a signer");
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+
+            if amount == 0 {
+                return Err(FaucetError::InvalidAmount.into());
+            }
+
+            let (faucet_config_pda, _faucet_bump_seed) =
+                Pubkey::find_program_address(&[b"faucet_config"], program_id);
+
+            if faucet_config_pda != *faucet_config_account.key
+                || faucet_config_account.owner != program_id
+            {
+                msg!("Faucet config account is not the correct PDA");
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            let faucet_config = load_state::<FaucetConfig>(&faucet_config_account.data.borrow())?;
+
+            //returns are deposits from the treasury's point of view
+            if faucet_config.is_paused(PAUSE_DEPOSITS) {
+                msg!("Deposits are paused");
+                return Err(FaucetError::OperationPaused.into());
+            }
+
+            let (user_claim_pda, _user_bump_seed) = Pubkey::find_program_address(
+                &[b"user_claim", user_account.key.as_ref()],
+                program_id,
+            );
+
+            if user_claim_pda != *user_claim_record_account.key
+                || user_claim_record_account.owner != program_id
+            {
+                msg!("Only users who claimed can return tokens, use Deposit instead");
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            //credit only counts when the tokens really are back in the treasury
+            faucet_config.check_treasury(faucet_treasury_account)?;
+
+            let transfer_instruction = transfer(
+                &TOKEN_PROGRAM_ID,
+                user_token_account.key,
+                faucet_treasury_account.key,
+                user_account.key,
+                &[],
+                amount,
+            )?;
+
+            invoke(
+                &transfer_instruction,
+                &[
+                    user_token_account.clone(),
+                    faucet_treasury_account.clone(),
+                    user_account.clone(),
+                    token_program.clone(),
+                ],
+            )?;
 
-        FaucetInstruction::ClaimTokens => {
-            msg!("Processing claim tokens request");
+            let mut user_record =
+                load_state::<UserClaimedRecord>(&user_claim_record_account.data.borrow())?;
 
-            //account iterator
-            let accounts_iter = &mut accounts.iter();
+            //credit applies to history in the current unit
+            let clock = Clock::get()?;
+            user_record.convert_rate_limit(faucet_config.cooldown_unit, &clock);
+            faucet_config.rate_limit.credit_return(
+                faucet_config.cooldown_seconds,
+                faucet_config.tokens_per_claim,
+                &mut user_record,
+                faucet_config.cooldown_unit.current_tick(&clock),
+                amount,
+            );
 
-            //user requesting tokens (must be a signer)
-            let user_account = next_account_info(accounts_iter)?;
-            if !user_account.is_signer {
-                msg!("User account must be a signer");
-                return Err(ProgramError::MissingRequiredSignature);
-            }
+            user_record.total_returned = user_record.total_returned.saturating_add(amount);
+            user_record.serialize(&mut &mut user_claim_record_account.data.borrow_mut()[..])?;
 
-            //user's claim record PDA(will create and update)
-            let user_claim_record_account = next_account_info(accounts_iter)?;
+            emit_event(&FaucetEvent::TokensReturned {
+                user: *user_account.key,
+                amount,
+                total_returned: user_record.total_returned,
+            })?;
 
-            //token account of user (to receive the tokens)
-            let user_token_account = next_account_info(accounts_iter)?;
+            msg!("Thanks for returning {} tokens!", amount);
+        }
 
-            // faucet treasury account token (tokens come from this)
-            let faucet_treasury_account = next_account_info(accounts_iter)?;
+        FaucetInstruction::WithdrawReferralBonus => {
+            msg!("Processing referral bonus withdrawal");
 
-            //faucet config account (contain settings)
-            let faucet_account_config = next_account_info(accounts_iter)?;
+            let accounts_iter = &mut accounts.iter();
 
-            // token program
+            let referrer_account = next_account_info(accounts_iter)?;
+            let referral_account = next_account_info(accounts_iter)?;
+            let referrer_token_account = next_account_info(accounts_iter)?;
+            let faucet_treasury_account = next_account_info(accounts_iter)?;
+            let faucet_config_account = next_account_info(accounts_iter)?;
             let token_program = next_account_info(accounts_iter)?;
 
-            //system program (needed to create user claim record if first time)
-            let system_program = next_account_info(accounts_iter)?;
+            if !referrer_account.is_signer {
+                msg!("Referrer must be a signer");
+                return Err(ProgramError::MissingRequiredSignature);
+            }
 
-            //load faucet config
-            let faucet_config = FaucetConfig::try_from_slice(&faucet_account_config.data.borrow())?;
+            let faucet_config_seed = b"faucet_config";
+            let (faucet_config_pda, faucet_bump_seed) =
+                Pubkey::find_program_address(&[faucet_config_seed], program_id);
 
-            let faucet_authority_account = next_account_info(accounts_iter)?; // Add this line
+            if faucet_config_pda != *faucet_config_account.key
+                || faucet_config_account.owner != program_id
+            {
+                msg!("Faucet config account is not the correct PDA");
+                return Err(ProgramError::InvalidAccountData);
+            }
 
-            //if faucet active or not
-            if !faucet_config.is_active {
+            let faucet_config = load_state::<FaucetConfig>(&faucet_config_account.data.borrow())?;
+            faucet_config.check_treasury(faucet_treasury_account)?;
+
+            //bonuses leave the treasury like claims do
+            if faucet_config.is_paused(PAUSE_CLAIMS) {
                 msg!("Faucet is currently inactive");
                 return Err(FaucetError::FaucetInactive.into());
             }
 
-            //PDA for user claim record
-            let user_claim_seed = b"user_claim";
-            let (user_claim_pda, user_bump_seed) = Pubkey::find_program_address(
-                &[user_claim_seed, user_account.key.as_ref()], //converts Pubkey to &[u8]
+            let (referral_pda, _referral_bump_seed) = Pubkey::find_program_address(
+                &[b"referral", referrer_account.key.as_ref()],
                 program_id,
             );
 
-            //verifying the passed account is the correct PDA
-            if user_claim_pda != *user_claim_record_account.key {
-                msg!("User claim record account is not the correct PDA");
+            if referral_pda != *referral_account.key || referral_account.owner != program_id {
+                msg!("Referral account is not the correct PDA");
                 return Err(ProgramError::InvalidAccountData);
             }
 
-            //create PDA for faucet config
-            let faucet_config_seed = b"faucet_config";
-            let (faucet_config_pda, faucet_bump_seed) =
-                Pubkey::find_program_address(&[faucet_config_seed], program_id);
-
-            //getting the current timestamp
-            let clock = Clock::get()?;
-            let current_time = clock.unix_timestamp;
-
-            //to check if the user's claim record exist
-            let mut user_record = if user_claim_record_account.data_len() == 0 {
-                msg!("User is claiming for the first time... Creating a new account!");
-
-                //creating the user claim record account
-                let user_record = UserClaimedRecord {
-                    user: *user_account.key,
-                    last_claim_time: 0, //no prev claim for the first timers
-                    total_claims: 0,
-                };
-
-                let required_space = borsh::to_vec(&user_record)?.len();
-                let rent = Rent::get()?;
-                let required_lamports = rent.minimum_balance(required_space);
-
-                let create_account_instruction = system_instruction::create_account(
-                    user_account.key,
-                    user_claim_record_account.key,
-                    required_lamports,
-                    required_space as u64,
-                    program_id,
-                );
+            let mut referral = load_state::<ReferralAccount>(&referral_account.data.borrow())?;
+            let amount = referral.claimable;
 
-                invoke_signed(
-                    &create_account_instruction,
-                    &[
-                        user_account.clone(),
-                        user_claim_record_account.clone(),
-                        system_program.clone(),
-                    ],
-                    &[&[
-                        user_claim_seed,
-                        user_account.key.as_ref(),
-                        &[user_bump_seed],
-                    ]],
-                )?;
-                user_record
-            } else {
-                //load existing user record
-                UserClaimedRecord::try_from_slice(&user_claim_record_account.data.borrow())?
-            };
+            if amount == 0 {
+                msg!("No referral bonus to withdraw");
+                return Err(FaucetError::InsufficientFunds.into());
+            }
 
-            //checking cooldown period
-            let time_since_last_claim = current_time - user_record.last_claim_time;
+            let referrer_token_data =
+                spl_token::state::Account::unpack(&referrer_token_account.data.borrow())
+                    .map_err(|_| ProgramError::InvalidAccountData)?;
 
-            if time_since_last_claim < faucet_config.cooldown_seconds {
-                let remaining_cooldown = faucet_config.cooldown_seconds - time_since_last_claim;
+            if referrer_token_data.mint != faucet_config.token_mint
+                || referrer_token_data.owner != *referrer_account.key
+            {
                 msg!(
-                    "Cooldown period not met! Please wait for {} seconds",
-                    remaining_cooldown
+                    "Referrer token account must hold the faucet mint and be owned by the referrer"
                 );
-                return Err(FaucetError::CooldownNotMet.into());
+                return Err(ProgramError::InvalidAccountData);
             }
 
-            msg!(
-                "Cooldown check passed! Checking if {} tokens available...",
-                faucet_config.tokens_per_claim
-            );
-
             let treasury_data =
                 spl_token::state::Account::unpack(&faucet_treasury_account.data.borrow())
                     .map_err(|_| ProgramError::InvalidAccountData)?;
 
-            if treasury_data.amount < faucet_config.tokens_per_claim {
+            if treasury_data.amount < amount {
                 msg!(
-                    "Treasury has {} tokens, but {} tokens requested!",
+                    "Treasury has {} tokens, but {} tokens are owed!",
                     treasury_data.amount,
-                    faucet_config.tokens_per_claim
+                    amount
                 );
                 return Err(FaucetError::InsufficientFunds.into());
             }
 
-            //creating token transfer instruction i.e. CPI
             let transfer_instruction = transfer(
                 &TOKEN_PROGRAM_ID,
-                faucet_treasury_account.key, //source token account
-                user_token_account.key,      //destination token account
-                &faucet_config_pda,          //authority (faucet config PDA)
+                faucet_treasury_account.key,
+                referrer_token_account.key,
+                &faucet_config_pda,
                 &[],
-                faucet_config.tokens_per_claim, //amount to transfer
-            )
-            .map_err(|e| {
-                msg!("Failed to create transfer instruction: {:?}", e);
-                ProgramError::InvalidInstructionData
-            })?;
+                amount,
+            )?;
 
-            //execute the token transfer via CPI with PDA signature
             invoke_signed(
                 &transfer_instruction,
                 &[
                     faucet_treasury_account.clone(),
-                    user_token_account.clone(),
-                    faucet_authority_account.clone(),
+                    referrer_token_account.clone(),
+                    faucet_config_account.clone(),
                     token_program.clone(),
                 ],
-                &[&[faucet_config_seed, &[faucet_bump_seed]]], //PDA signature
+                &[&[faucet_config_seed, &[faucet_bump_seed]]],
             )?;
 
-            //updating user's claim records
-            user_record.last_claim_time = current_time;
-            user_record.total_claims += 1;
-
-            //saving the updated record
-            user_record.serialize(&mut &mut user_claim_record_account.data.borrow_mut()[..])?;
+            referral.claimable = 0;
+            referral.serialize(&mut &mut referral_account.data.borrow_mut()[..])?;
 
-            msg!("Tokens have been transferred successfully!");
-            msg!("User: {}", user_account.key);
-            msg!("Amount: {}", faucet_config.tokens_per_claim);
-            msg!("Total user claims: {}", user_record.total_claims);
+            msg!(
+                "Paid {} tokens of referral bonus to {}",
+                amount,
+                referrer_account.key
+            );
         }
 
-        FaucetInstruction::UpdateFaucetConfig {
-            new_tokens_per_claim,
-            new_cooldown_seconds,
-            new_is_active,
-        } => {
-            msg!("Processing faucet update request!");
+        FaucetInstruction::Release => {
+            msg!("Processing vesting release");
 
             let accounts_iter = &mut accounts.iter();
 
-            let admin_account = next_account_info(accounts_iter)?;
-            let faucet_config_account = next_account_info(accounts_iter)?;
+            let user_account = next_account_info(accounts_iter)?;
+            let vesting_account = next_account_info(accounts_iter)?;
+            let vesting_escrow_account = next_account_info(accounts_iter)?;
+            let user_token_account = next_account_info(accounts_iter)?;
+            let token_program = next_account_info(accounts_iter)?;
 
-            // Validate admin is signer
-            if !admin_account.is_signer {
-                msg!("Admin must be the signer!");
+            if !user_account.is_signer {
+                msg!("User account must be a signer");
                 return Err(ProgramError::MissingRequiredSignature);
             }
 
-            //loading the current config
-            let mut faucet_config =
-                FaucetConfig::try_from_slice(&faucet_config_account.data.borrow())?;
-
-            //checking if the caller is the real admin
-            if admin_account.key != &faucet_config.admin {
-                msg!("Unauthorized admin access attempt!");
-                msg!("Expected admin: {}", faucet_config.admin);
-                msg!("Actual caller: {}", admin_account.key);
-                return Err(FaucetError::UnauthorizedAdmin.into());
-            }
-
-            msg!("Admin account verified!");
+            let vesting_seed = b"vesting";
+            let (vesting_pda, vesting_bump_seed) = Pubkey::find_program_address(
+                &[vesting_seed, user_account.key.as_ref()],
+                program_id,
+            );
+            let (vesting_escrow_pda, _vesting_escrow_bump_seed) = Pubkey::find_program_address(
+                &[b"vesting_escrow", user_account.key.as_ref()],
+                program_id,
+            );
 
-            if let Some(tokens) = new_tokens_per_claim {
-                faucet_config.tokens_per_claim = tokens;
-                msg!("Updated tokens per claim to: {}", tokens);
+            if vesting_pda != *vesting_account.key
+                || vesting_account.owner != program_id
+                || vesting_escrow_pda != *vesting_escrow_account.key
+            {
+                msg!("Vesting accounts are not the correct PDAs");
+                return Err(ProgramError::InvalidAccountData);
             }
 
-            if let Some(cooldown) = new_cooldown_seconds {
-                faucet_config.cooldown_seconds = cooldown as i64;
-                msg!("Updated cooldown to: {} seconds", cooldown);
-            }
+            let mut vesting = load_state::<VestingAccount>(&vesting_account.data.borrow())?;
 
-            if let Some(active) = new_is_active {
-                faucet_config.is_active = active;
+            let current_time = Clock::get()?.unix_timestamp;
+            let amount = vesting.releasable(current_time);
+            if amount == 0 {
                 msg!(
-                    "Updated faucet status to: {}",
-                    if active { "ACTIVE" } else { "INACTIVE" }
+                    "Nothing vested yet, {} of {} tokens released",
+                    vesting.released,
+                    vesting.total
                 );
+                return Err(FaucetError::NothingToRelease.into());
             }
 
-            faucet_config.serialize(&mut &mut faucet_config_account.data.borrow_mut()[..])?;
+            invoke_signed(
+                &transfer(
+                    &TOKEN_PROGRAM_ID,
+                    vesting_escrow_account.key,
+                    user_token_account.key,
+                    vesting_account.key,
+                    &[],
+                    amount,
+                )?,
+                &[
+                    vesting_escrow_account.clone(),
+                    user_token_account.clone(),
+                    vesting_account.clone(),
+                    token_program.clone(),
+                ],
+                &[&[
+                    vesting_seed,
+                    user_account.key.as_ref(),
+                    &[vesting_bump_seed],
+                ]],
+            )?;
 
-            msg!("Faucet Configuration updated successfully!")
-        }
+            vesting.released += amount;
+            vesting.serialize(&mut &mut vesting_account.data.borrow_mut()[..])?;
 
-        FaucetInstruction::EmergencyPause => {
-            msg!("Emergency Pause has been requested!");
+            emit_event(&FaucetEvent::VestingReleased {
+                user: *user_account.key,
+                amount,
+                remaining: vesting.total - vesting.released,
+            })?;
+
+            msg!("Released {} tokens to {}", amount, user_account.key);
+        }
+        FaucetInstruction::Migrate => {
+            msg!("Processing migration");
 
             let accounts_iter = &mut accounts.iter();
 
             let admin_account = next_account_info(accounts_iter)?;
             let faucet_config_account = next_account_info(accounts_iter)?;
+            let system_program = next_account_info(accounts_iter)?;
+            let faucet_treasury_account = next_account_info(accounts_iter)?;
+            let record_accounts = accounts_iter.as_slice();
 
-            //validating admin signature
             if !admin_account.is_signer {
+                msg!("Admin must be the signer!");
                 return Err(ProgramError::MissingRequiredSignature);
             }
 
-            //loading config and verifying the admin
-            let mut faucet_config =
-                FaucetConfig::try_from_slice(&faucet_config_account.data.borrow())?;
-
-            if admin_account.key != &faucet_config.admin {
-                msg!(
-                    "Unauthorized attempt to pause the faucet from: {}",
-                    admin_account.key
-                );
-
-                return Err(FaucetError::UnauthorizedAdmin.into());
-            }
-
-            faucet_config.is_active = false;
-            faucet_config.serialize(&mut &mut faucet_config_account.data.borrow_mut()[..])?;
-
-            msg!("Faucet has been pause by the admin!");
-            msg!("Admin: {}", admin_account.key);
-            msg!("All tokens have been blocked until the Faucet resumes!");
-        }
-
-        FaucetInstruction::WithdrawTreasury { amount } => {
-            msg!("Processing treasury withdrawal of: {} tokens", amount);
-
-            let accounts_iter = &mut accounts.iter();
-
-            let admin_account = next_account_info(accounts_iter)?;
-            let faucet_config_account = next_account_info(accounts_iter)?;
-            let faucet_authority_account = next_account_info(accounts_iter)?; // PDA for signing
-            let faucet_treasury_account = next_account_info(accounts_iter)?; // Source
-            let admin_token_account = next_account_info(accounts_iter)?; // Destination
-            let token_program = next_account_info(accounts_iter)?;
+            let (faucet_config_pda, _faucet_bump_seed) =
+                Pubkey::find_program_address(&[b"faucet_config"], program_id);
 
-            //security checks
-            if !admin_account.is_signer {
-                return Err(ProgramError::MissingRequiredSignature);
+            if faucet_config_pda != *faucet_config_account.key
+                || faucet_config_account.owner != program_id
+            {
+                msg!("Faucet config account is not the correct PDA");
+                return Err(ProgramError::InvalidAccountData);
             }
 
-            let faucet_config = FaucetConfig::try_from_slice(&faucet_config_account.data.borrow())?;
+            //the first release sized the config to fit exactly
+            let legacy_config = if faucet_config_account.data_len() == LegacyFaucetConfig::LEN {
+                Some(load_state::<LegacyFaucetConfig>(
+                    &faucet_config_account.data.borrow(),
+                )?)
+            } else {
+                None
+            };
+            let admin = match &legacy_config {
+                Some(legacy) => legacy.admin,
+                None => load_state::<FaucetConfig>(&faucet_config_account.data.borrow())?.admin,
+            };
 
-            if admin_account.key != &faucet_config.admin {
+            if *admin_account.key != admin {
+                msg!("Unauthorized admin access attempt!");
                 return Err(FaucetError::UnauthorizedAdmin.into());
             }
 
-            // Check treasury has enough tokens
-            let treasury_data =
-                spl_token::state::Account::unpack(&faucet_treasury_account.data.borrow())
-                    .map_err(|_| FaucetError::InsufficientFunds)?;
+            if let Some(legacy) = legacy_config {
+                //the first release never recorded its treasury
+                validate_treasury(
+                    faucet_treasury_account,
+                    &legacy.token_mint,
+                    &faucet_config_pda,
+                )?;
+                let faucet_config =
+                    legacy.migrate(*faucet_treasury_account.key, Clock::get()?.unix_timestamp);
 
-            if treasury_data.amount < amount {
+                grow_account(
+                    faucet_config_account,
+                    admin_account,
+                    system_program,
+                    FaucetConfig::SPACE,
+                )?;
+                faucet_config.serialize(&mut &mut faucet_config_account.data.borrow_mut()[..])?;
                 msg!(
-                    "Cannot withdraw {} tokens, treasury only has {}",
-                    amount,
-                    treasury_data.amount
+                    "Faucet config migrated, paused flags: {:#010b}",
+                    faucet_config.paused
                 );
-                return Err(FaucetError::InsufficientFunds.into());
             }
 
-            // Create withdrawal transfer
-            let faucet_config_seed = b"faucet_config";
-            let (_faucet_config_pda, faucet_bump_seed) =
-                Pubkey::find_program_address(&[faucet_config_seed], program_id);
+            let mut migrated = 0;
+            for record_account in record_accounts {
+                if record_account.owner != program_id
+                    || record_account.data_len() != LegacyClaimRecord::LEN
+                {
+                    msg!(
+                        "Skipping {}: not a first release claim record",
+                        record_account.key
+                    );
+                    continue;
+                }
 
-            let transfer_instruction = spl_token::instruction::transfer(
-                &spl_token::id(),
-                faucet_treasury_account.key,  // From treasury
-                admin_token_account.key,      // To admin
-                faucet_authority_account.key, // Authority (PDA)
-                &[],
-                amount,
-            )?;
+                let legacy = load_state::<LegacyClaimRecord>(&record_account.data.borrow())?;
+                let (user_claim_pda, _user_bump_seed) = Pubkey::find_program_address(
+                    &[b"user_claim", legacy.user.as_ref()],
+                    program_id,
+                );
 
-            invoke_signed(
-                &transfer_instruction,
-                &[
-                    faucet_treasury_account.clone(),
-                    admin_token_account.clone(),
-                    faucet_authority_account.clone(),
-                    token_program.clone(),
-                ],
-                &[&[faucet_config_seed, &[faucet_bump_seed]]],
-            )?;
+                if user_claim_pda != *record_account.key {
+                    msg!("User claim record account is not the correct PDA");
+                    return Err(ProgramError::InvalidAccountData);
+                }
 
-            msg!("✅ Treasury withdrawal successful!");
-            msg!("Amount: {} tokens", amount);
-            msg!("Withdrawn to: {}", admin_token_account.key);
+                grow_account(
+                    record_account,
+                    admin_account,
+                    system_program,
+                    UserClaimedRecord::SPACE,
+                )?;
+                legacy
+                    .migrate()
+                    .serialize(&mut &mut record_account.data.borrow_mut()[..])?;
+                migrated += 1;
+            }
+
+            msg!("Migrated {} claim records", migrated);
         }
     }
     Ok(())
@@ -0,0 +1,95 @@
+use token_faucet_backend::{EmissionCurve, RateLimitPolicy};
+
+#[test]
+fn constant_ignores_age_and_treasury() {
+    let curve = EmissionCurve::Constant;
+    assert_eq!(curve.evaluate(1_000, 0, 0), 1_000);
+    assert_eq!(curve.evaluate(1_000, 10_000_000, 5), 1_000);
+}
+
+#[test]
+fn step_halving_halves_each_interval() {
+    let curve = EmissionCurve::StepHalving {
+        interval_seconds: 100,
+    };
+    assert_eq!(curve.evaluate(1_000, 0, 0), 1_000);
+    assert_eq!(curve.evaluate(1_000, 99, 0), 1_000);
+    assert_eq!(curve.evaluate(1_000, 100, 0), 500);
+    assert_eq!(curve.evaluate(1_000, 250, 0), 250);
+    assert_eq!(curve.evaluate(1_000, 100 * 64, 0), 0);
+}
+
+#[test]
+fn linear_decay_reaches_floor() {
+    let curve = EmissionCurve::LinearDecay {
+        duration_seconds: 1_000,
+        floor: 100,
+    };
+    assert_eq!(curve.evaluate(1_100, 0, 0), 1_100);
+    assert_eq!(curve.evaluate(1_100, 500, 0), 600);
+    assert_eq!(curve.evaluate(1_100, 1_000, 0), 100);
+    assert_eq!(curve.evaluate(1_100, 5_000, 0), 100);
+
+    //curve never increases with age
+    let mut last = u64::MAX;
+    for age in (0..1_200).step_by(7) {
+        let amount = curve.evaluate(1_100, age, 0);
+        assert!(amount <= last);
+        last = amount;
+    }
+}
+
+#[test]
+fn treasury_proportional_scales_below_full_at() {
+    let curve = EmissionCurve::TreasuryProportional { full_at: 10_000 };
+    assert_eq!(curve.evaluate(1_000, 0, 50_000), 1_000);
+    assert_eq!(curve.evaluate(1_000, 0, 10_000), 1_000);
+    assert_eq!(curve.evaluate(1_000, 0, 2_500), 250);
+    assert_eq!(curve.evaluate(1_000, 0, 0), 0);
+}
+
+#[test]
+fn degenerate_curves_are_rejected() {
+    assert!(
+        EmissionCurve::StepHalving {
+            interval_seconds: 0
+        }
+        .validate()
+        .is_err()
+    );
+    assert!(
+        EmissionCurve::TreasuryProportional { full_at: 0 }
+            .validate()
+            .is_err()
+    );
+    assert!(EmissionCurve::Constant.validate().is_ok());
+}
+
+#[test]
+fn decay_shrinks_token_buckets_only() {
+    let bucket = RateLimitPolicy::TokenBucket {
+        refill_amount: 1_000,
+        refill_interval: 60,
+        burst: 2_000,
+    };
+    assert_eq!(
+        bucket.decayed(1_000, 250),
+        RateLimitPolicy::TokenBucket {
+            refill_amount: 250,
+            refill_interval: 60,
+            burst: 500,
+        }
+    );
+    assert_eq!(bucket.decayed(1_000, 1_000), bucket);
+
+    for policy in [
+        RateLimitPolicy::FixedCooldown,
+        RateLimitPolicy::ConfigBucket,
+        RateLimitPolicy::SlidingWindow {
+            max_claims: 2,
+            window_seconds: 60,
+        },
+    ] {
+        assert_eq!(policy.decayed(1_000, 250), policy);
+    }
+}
@@ -1,27 +1,36 @@
-use borsh::{BorshDeserialize, BorshSerialize};
+use borsh::BorshDeserialize;
 use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::ProgramResult,
     example_mocks::solana_sdk::system_instruction,
     instruction::{AccountMeta, Instruction},
+    program::invoke,
+    program_error::ProgramError,
     pubkey::Pubkey,
-    system_program,
 };
 use solana_program_test::*;
 use solana_sdk::{
     //provides transaction building tools
-    account::Account,
-    address_lookup_table::program,
+    account::{Account, AccountSharedData},
+    instruction::InstructionError,
     program_pack::Pack,
     signature::{Keypair, Signer},
-    signer::keypair,
-    sysvar::recent_blockhashes,
-    transaction::Transaction,
-    vote::instruction,
+    transaction::{Transaction, TransactionError},
 };
+#[allow(deprecated)]
+use solana_sdk::{ed25519_instruction::new_ed25519_instruction_with_signature, system_program};
 use spl_token::{
     instruction::{initialize_mint, mint_to},
-    state::{Account as TokenAccount, Mint},
+    state::{Account as TokenAccount, AccountState, Mint},
+};
+use token_faucet_backend::{
+    BasketEntry, ClaimMode, ClaimVoucher, ConfigUpdate, CooldownUnit, DistributorAllowance,
+    DonorContribution, EmissionCurve, FaucetConfig, FaucetInstruction, LegacyClaimRecord,
+    LegacyFaucetConfig, Lottery, LotteryTier, MAX_AIRDROP_RECIPIENTS, PAUSE_ALL, PAUSE_CLAIMS,
+    PAUSE_CONFIG_UPDATES, PAUSE_DEPOSITS, PauseAction, PauseLog, RateLimitPolicy, ReferralAccount,
+    StreakPolicy, TokenGate, UserClaimedRecord, VestingSchedule, airdrop_instructions,
 };
-use token_faucet_backend::{FaucetConfig, FaucetInstruction, UserClaimedRecord};
 
 #[tokio::test] //handles async/await
 // Init → Treasury → First Claim
@@ -63,7 +72,7 @@ async fn test_initialize_faucet() {
     program_test.add_account(user_keypair.pubkey(), user_account);
 
     //starting the test env
-    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    let (banks_client, payer, recent_blockhash) = program_test.start().await;
 
     println!("Starting test blockchin via admin account");
     println!("Admin address: {}", admin_keypair.pubkey());
@@ -116,6 +125,45 @@ async fn test_initialize_faucet() {
     let (faucet_config_pda, _bump) =
         Pubkey::find_program_address(&[faucet_config_seed], &program_id);
 
+    //calculating rent for the token account
+    let token_account_rent = banks_client.get_rent().await.unwrap();
+    let token_account_lamports = token_account_rent.minimum_balance(TokenAccount::LEN);
+
+    // faucet treasury account (place for faucet to store the tokens)
+    let faucet_treasury_account = Keypair::new();
+
+    let create_faucet_treasury_ix = system_instruction::create_account(
+        &payer.pubkey(),
+        &faucet_treasury_account.pubkey(),
+        token_account_lamports,
+        TokenAccount::LEN as u64,
+        &spl_token::id(),
+    );
+
+    let init_faucet_treasury_ix = spl_token::instruction::initialize_account(
+        &spl_token::id(),
+        &faucet_treasury_account.pubkey(),
+        &mint_keypair.pubkey(),
+        &faucet_config_pda, // Use faucet PDA as owner instead of admin
+    )
+    .unwrap();
+
+    let mut treasury_tx = Transaction::new_with_payer(
+        &[create_faucet_treasury_ix, init_faucet_treasury_ix],
+        Some(&payer.pubkey()),
+    );
+    treasury_tx.sign(&[&payer, &faucet_treasury_account], recent_blockhash);
+
+    let res = banks_client.process_transaction(treasury_tx).await;
+    assert!(res.is_ok(), "Failed to create treasury account: {:?}", res);
+
+    println!("Faucet treasury account created successfully!");
+    println!(
+        "Treasury account address: {}",
+        faucet_treasury_account.pubkey()
+    );
+    println!("Treasury account owner: {}", faucet_config_pda);
+
     //initializde faucet instruction data
     let initialize_faucet = FaucetInstruction::InitializeFaucet {
         tokens_per_claim: 1000000000,
@@ -130,6 +178,7 @@ async fn test_initialize_faucet() {
             AccountMeta::new(faucet_config_pda, false),
             AccountMeta::new_readonly(mint_keypair.pubkey(), false),
             AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(faucet_treasury_account.pubkey(), false),
         ],
         data: borsh::to_vec(&initialize_faucet).unwrap(),
     };
@@ -150,14 +199,14 @@ async fn test_initialize_faucet() {
         .await
         .unwrap()
         .unwrap();
-    let faucet_config = FaucetConfig::try_from_slice(&config_account.data).unwrap();
+    let faucet_config = FaucetConfig::deserialize(&mut config_account.data.as_slice()).unwrap();
 
     //checking all stored values
     assert_eq!(faucet_config.admin, admin_keypair.pubkey());
     assert_eq!(faucet_config.token_mint, mint_keypair.pubkey());
-    assert_eq!(faucet_config.tokens_per_claim, 1000_000_000);
+    assert_eq!(faucet_config.tokens_per_claim, 1_000_000_000);
     assert_eq!(faucet_config.cooldown_seconds, 60);
-    assert_eq!(faucet_config.is_active, true);
+    assert_eq!(faucet_config.paused, 0);
 
     println!("Faucet Configuration Verified");
     println!("Admin: {}", faucet_config.admin);
@@ -168,7 +217,7 @@ async fn test_initialize_faucet() {
         faucet_config.tokens_per_claim as f64 / 1_000_000.0
     );
     println!("Cooldown: {} seconds", faucet_config.cooldown_seconds);
-    println!("Active: {}", faucet_config.is_active);
+    println!("Paused flags: {:#010b}", faucet_config.paused);
 
     println!("\n Testing claiming tokens...");
 
@@ -180,10 +229,6 @@ async fn test_initialize_faucet() {
 
     //creating user's token account
 
-    //calculating rent for the token account
-    let token_account_rent = banks_client.get_rent().await.unwrap();
-    let token_account_lamports = token_account_rent.minimum_balance(TokenAccount::LEN);
-
     //creating user's token account (space allocation)
     let create_user_token_ix = system_instruction::create_account(
         &payer.pubkey(), //pays the account
@@ -223,41 +268,6 @@ async fn test_initialize_faucet() {
     println!("Owner: {}", user_keypair.pubkey());
     println!("Token type: {}", mint_keypair.pubkey());
 
-    // faucet treasury account (place for faucet to store the tokens)
-    let faucet_treasury_account = Keypair::new();
-
-    let create_faucet_treasury_ix = system_instruction::create_account(
-        &payer.pubkey(),
-        &faucet_treasury_account.pubkey(),
-        token_account_lamports,
-        TokenAccount::LEN as u64,
-        &spl_token::id(),
-    );
-
-    let init_faucet_treasury_ix = spl_token::instruction::initialize_account(
-        &spl_token::id(),
-        &faucet_treasury_account.pubkey(),
-        &mint_keypair.pubkey(),
-        &faucet_config_pda, // Use faucet PDA as owner instead of admin
-    )
-    .unwrap();
-
-    let mut treasury_tx = Transaction::new_with_payer(
-        &[create_faucet_treasury_ix, init_faucet_treasury_ix],
-        Some(&payer.pubkey()),
-    );
-    treasury_tx.sign(&[&payer, &faucet_treasury_account], recent_blockhash);
-
-    let res = banks_client.process_transaction(treasury_tx).await;
-    assert!(res.is_ok(), "Failed to create treasury account: {:?}", res);
-
-    println!("Faucet treasury account created successfully!");
-    println!(
-        "Treasury account address: {}",
-        faucet_treasury_account.pubkey()
-    );
-    println!("Treasury account owner: {}", faucet_config_pda);
-
     //minting tokens into the treasury to give them away
 
     //creating mint_to instruction to put tokens in the treasury
@@ -267,7 +277,7 @@ async fn test_initialize_faucet() {
         &faucet_treasury_account.pubkey(),
         &admin_keypair.pubkey(),
         &[],
-        1000_000_000,
+        1_000_000_000,
     )
     .unwrap();
 
@@ -294,7 +304,10 @@ async fn test_initialize_faucet() {
     );
 
     //token claim instruction
-    let claim_instruction = FaucetInstruction::ClaimTokens;
+    let claim_instruction = FaucetInstruction::ClaimTokens {
+        amount: None,
+        referrer: None,
+    };
 
     let claim_ix = Instruction {
         program_id,
@@ -304,9 +317,9 @@ async fn test_initialize_faucet() {
             AccountMeta::new(user_token_account.pubkey(), false), //will receive tokens here
             AccountMeta::new(faucet_treasury_account.pubkey(), false), //source of tokens
             AccountMeta::new(faucet_config_pda, false),    //faucet config account
-            AccountMeta::new_readonly(admin_keypair.pubkey(), false),
             AccountMeta::new_readonly(spl_token::id(), false),
             AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(faucet_config_pda, false), //faucet authority
         ],
         data: borsh::to_vec(&claim_instruction).unwrap(),
     };
@@ -341,7 +354,7 @@ async fn test_cooldown_enforcement() {
 
     //funding the accounts
     let admin_account = Account {
-        lamports: 1000_000_000,
+        lamports: 1_000_000_000,
         data: vec![],
         owner: system_program::id(),
         executable: false,
@@ -387,32 +400,9 @@ async fn test_cooldown_enforcement() {
 
     println!("Mint created for cooldown test");
 
-    // Initialize faucet
     let faucet_config_seed = b"faucet_config";
     let (faucet_config_pda, _) = Pubkey::find_program_address(&[faucet_config_seed], &program_id);
 
-    let initialize_faucet = FaucetInstruction::InitializeFaucet {
-        tokens_per_claim: 1000000000,
-        cooldown_seconds: 60, // 60 second cooldown for testing
-    };
-
-    let init_ix = Instruction {
-        program_id,
-        accounts: vec![
-            AccountMeta::new(admin_keypair.pubkey(), true),
-            AccountMeta::new(faucet_config_pda, false),
-            AccountMeta::new_readonly(mint_keypair.pubkey(), false),
-            AccountMeta::new_readonly(system_program::id(), false),
-        ],
-        data: borsh::to_vec(&initialize_faucet).unwrap(),
-    };
-
-    let mut init_tx = Transaction::new_with_payer(&[init_ix], Some(&payer.pubkey()));
-    init_tx.sign(&[&payer, &admin_keypair], recent_blockhash);
-    banks_client.process_transaction(init_tx).await.unwrap();
-
-    println!("Faucet initialized for cooldown test");
-
     let faucet_treasury_account = Keypair::new();
     let token_account_rent = banks_client.get_rent().await.unwrap();
     let token_account_lamports = token_account_rent.minimum_balance(TokenAccount::LEN);
@@ -441,6 +431,30 @@ async fn test_cooldown_enforcement() {
     treasury_tx.sign(&[&payer, &faucet_treasury_account], recent_blockhash);
     banks_client.process_transaction(treasury_tx).await.unwrap();
 
+    // Initialize faucet
+    let initialize_faucet = FaucetInstruction::InitializeFaucet {
+        tokens_per_claim: 1000000000,
+        cooldown_seconds: 60, // 60 second cooldown for testing
+    };
+
+    let init_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(admin_keypair.pubkey(), true),
+            AccountMeta::new(faucet_config_pda, false),
+            AccountMeta::new_readonly(mint_keypair.pubkey(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(faucet_treasury_account.pubkey(), false),
+        ],
+        data: borsh::to_vec(&initialize_faucet).unwrap(),
+    };
+
+    let mut init_tx = Transaction::new_with_payer(&[init_ix], Some(&payer.pubkey()));
+    init_tx.sign(&[&payer, &admin_keypair], recent_blockhash);
+    banks_client.process_transaction(init_tx).await.unwrap();
+
+    println!("Faucet initialized for cooldown test");
+
     // Mint tokens to treasury
     let mint_to_ix = mint_to(
         &spl_token::id(),
@@ -496,7 +510,10 @@ async fn test_cooldown_enforcement() {
 
     println!("COOLDOWN TEST: Attempting first claim...");
 
-    let claim_instruction = FaucetInstruction::ClaimTokens;
+    let claim_instruction = FaucetInstruction::ClaimTokens {
+        amount: None,
+        referrer: None,
+    };
 
     let first_claim_ix = Instruction {
         program_id,
@@ -506,9 +523,9 @@ async fn test_cooldown_enforcement() {
             AccountMeta::new(user_token_account.pubkey(), false),
             AccountMeta::new(faucet_treasury_account.pubkey(), false),
             AccountMeta::new(faucet_config_pda, false),
-            AccountMeta::new_readonly(admin_keypair.pubkey(), false),
             AccountMeta::new_readonly(spl_token::id(), false),
             AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(faucet_config_pda, false), //faucet authority
         ],
         data: borsh::to_vec(&claim_instruction).unwrap(),
     };
@@ -565,16 +582,22 @@ async fn test_cooldown_enforcement() {
             AccountMeta::new(user_token_account.pubkey(), false),
             AccountMeta::new(faucet_treasury_account.pubkey(), false),
             AccountMeta::new(faucet_config_pda, false),
-            AccountMeta::new_readonly(admin_keypair.pubkey(), false),
             AccountMeta::new_readonly(spl_token::id(), false),
             AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(faucet_config_pda, false), //faucet authority
         ],
         data: borsh::to_vec(&claim_instruction).unwrap(), // Same instruction
     };
 
+    //fresh blockhash so the retry isn't deduplicated as the same transaction
+    let second_blockhash = banks_client
+        .get_new_latest_blockhash(&recent_blockhash)
+        .await
+        .unwrap();
+
     let mut second_claim_tx =
         Transaction::new_with_payer(&[second_claim_ix], Some(&payer.pubkey()));
-    second_claim_tx.sign(&[&payer, &user_keypair], recent_blockhash);
+    second_claim_tx.sign(&[&payer, &user_keypair], second_blockhash);
 
     let second_result = banks_client.process_transaction(second_claim_tx).await;
 
@@ -603,13 +626,13 @@ async fn test_cooldown_enforcement() {
     let user_balance_after = TokenAccount::unpack(&user_token_data_after.data).unwrap();
 
     assert_eq!(
-        user_balance_after.amount, 1000_000_000,
+        user_balance_after.amount, 1_000_000_000,
         "User balance should be unchanged after the claim failed!"
     );
 
     println!(
         "User balance unchanged: {} tokens",
-        user_balance_after.amount as f64 / 1000_000.0
+        user_balance_after.amount as f64 / 1_000_000.0
     );
 
     //verifying the claim record
@@ -618,7 +641,8 @@ async fn test_cooldown_enforcement() {
         .await
         .unwrap()
         .unwrap();
-    let claim_record = UserClaimedRecord::try_from_slice(&claim_record_data.data).unwrap();
+    let claim_record =
+        UserClaimedRecord::deserialize(&mut claim_record_data.data.as_slice()).unwrap();
 
     assert_eq!(
         claim_record.total_claims, 1,
@@ -633,3 +657,2808 @@ async fn test_cooldown_enforcement() {
 
     println!("Cooldown Test Completed!")
 }
+
+//shared setup for the tests below: mint, initialized faucet and a funded treasury
+struct FaucetTestEnv {
+    program_id: Pubkey,
+    context: ProgramTestContext,
+    admin: Keypair,
+    mint: Keypair,
+    faucet_config_pda: Pubkey,
+    treasury: Pubkey,
+}
+
+//stand-in for a bot's wrapper program
+const RELAY_PROGRAM_ID: Pubkey = Pubkey::new_from_array([7; 32]);
+
+//forwards its instruction data to the program passed as the first account
+fn relay_process_instruction(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let (target, forwarded) = accounts
+        .split_first()
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let instruction = Instruction {
+        program_id: *target.key,
+        accounts: forwarded
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: *account.key,
+                is_signer: account.is_signer,
+                is_writable: account.is_writable,
+            })
+            .collect(),
+        data: data.to_vec(),
+    };
+    invoke(&instruction, accounts)
+}
+
+async fn setup_faucet(
+    tokens_per_claim: u64,
+    cooldown_seconds: i64,
+    treasury_amount: u64,
+) -> FaucetTestEnv {
+    let program_id = Pubkey::new_unique();
+    let mut program_test = ProgramTest::new(
+        "token_faucet_backend",
+        program_id,
+        processor!(token_faucet_backend::process_instruction),
+    );
+    program_test.add_program(
+        "relay",
+        RELAY_PROGRAM_ID,
+        processor!(relay_process_instruction),
+    );
+    let context = program_test.start_with_context().await;
+
+    let (faucet_config_pda, _) = Pubkey::find_program_address(&[b"faucet_config"], &program_id);
+
+    let mut env = FaucetTestEnv {
+        program_id,
+        context,
+        admin: Keypair::new(),
+        mint: Keypair::new(),
+        faucet_config_pda,
+        treasury: Pubkey::default(),
+    };
+
+    let admin = env.admin.pubkey();
+    fund(&mut env, &admin, 1_000_000_000).await;
+
+    let rent = env.context.banks_client.get_rent().await.unwrap();
+    let payer = env.context.payer.pubkey();
+    let create_mint_ix = system_instruction::create_account(
+        &payer,
+        &env.mint.pubkey(),
+        rent.minimum_balance(Mint::LEN),
+        Mint::LEN as u64,
+        &spl_token::id(),
+    );
+    let init_mint_ix =
+        initialize_mint(&spl_token::id(), &env.mint.pubkey(), &admin, None, 6).unwrap();
+    let mint = env.mint.insecure_clone();
+    send(&mut env, &[create_mint_ix, init_mint_ix], &[&mint])
+        .await
+        .unwrap();
+
+    //the config pins the treasury, so it exists before the faucet does
+    env.treasury = create_token_account(&mut env, &faucet_config_pda).await;
+
+    let init_ix = Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(admin, true),
+            AccountMeta::new(faucet_config_pda, false),
+            AccountMeta::new_readonly(mint.pubkey(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(env.treasury, false),
+        ],
+        data: borsh::to_vec(&FaucetInstruction::InitializeFaucet {
+            tokens_per_claim,
+            cooldown_seconds,
+        })
+        .unwrap(),
+    };
+    let admin_keypair = env.admin.insecure_clone();
+    send(&mut env, &[init_ix], &[&admin_keypair]).await.unwrap();
+
+    let treasury = env.treasury;
+    mint_tokens(&mut env, &treasury, treasury_amount).await;
+
+    env
+}
+
+//signs with the payer plus extra signers on a fresh blockhash
+async fn send(
+    env: &mut FaucetTestEnv,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    let blockhash = env
+        .context
+        .banks_client
+        .get_new_latest_blockhash(&env.context.last_blockhash)
+        .await
+        .unwrap();
+    env.context.last_blockhash = blockhash;
+
+    let mut all_signers = vec![&env.context.payer];
+    all_signers.extend_from_slice(signers);
+
+    let mut tx = Transaction::new_with_payer(instructions, Some(&env.context.payer.pubkey()));
+    tx.sign(&all_signers, blockhash);
+    env.context.banks_client.process_transaction(tx).await
+}
+
+async fn fund(env: &mut FaucetTestEnv, to: &Pubkey, lamports: u64) {
+    let transfer_ix = system_instruction::transfer(&env.context.payer.pubkey(), to, lamports);
+    send(env, &[transfer_ix], &[]).await.unwrap();
+}
+
+async fn create_token_account(env: &mut FaucetTestEnv, owner: &Pubkey) -> Pubkey {
+    let mint = env.mint.pubkey();
+    create_token_account_for_mint(env, &mint, owner).await
+}
+
+async fn create_token_account_for_mint(
+    env: &mut FaucetTestEnv,
+    mint: &Pubkey,
+    owner: &Pubkey,
+) -> Pubkey {
+    let token_account = Keypair::new();
+    let rent = env.context.banks_client.get_rent().await.unwrap();
+
+    let create_ix = system_instruction::create_account(
+        &env.context.payer.pubkey(),
+        &token_account.pubkey(),
+        rent.minimum_balance(TokenAccount::LEN),
+        TokenAccount::LEN as u64,
+        &spl_token::id(),
+    );
+    let init_ix = spl_token::instruction::initialize_account(
+        &spl_token::id(),
+        &token_account.pubkey(),
+        mint,
+        owner,
+    )
+    .unwrap();
+
+    send(env, &[create_ix, init_ix], &[&token_account])
+        .await
+        .unwrap();
+    token_account.pubkey()
+}
+
+//extra mint with the admin as mint authority
+async fn create_mint(env: &mut FaucetTestEnv) -> Pubkey {
+    let mint = Keypair::new();
+    let rent = env.context.banks_client.get_rent().await.unwrap();
+
+    let create_ix = system_instruction::create_account(
+        &env.context.payer.pubkey(),
+        &mint.pubkey(),
+        rent.minimum_balance(Mint::LEN),
+        Mint::LEN as u64,
+        &spl_token::id(),
+    );
+    let init_ix = initialize_mint(
+        &spl_token::id(),
+        &mint.pubkey(),
+        &env.admin.pubkey(),
+        None,
+        0,
+    )
+    .unwrap();
+
+    send(env, &[create_ix, init_ix], &[&mint]).await.unwrap();
+    mint.pubkey()
+}
+
+async fn mint_tokens(env: &mut FaucetTestEnv, to: &Pubkey, amount: u64) {
+    let mint = env.mint.pubkey();
+    mint_tokens_of(env, &mint, to, amount).await;
+}
+
+async fn mint_tokens_of(env: &mut FaucetTestEnv, mint: &Pubkey, to: &Pubkey, amount: u64) {
+    let mint_to_ix = mint_to(&spl_token::id(), mint, to, &env.admin.pubkey(), &[], amount).unwrap();
+    let admin_keypair = env.admin.insecure_clone();
+    send(env, &[mint_to_ix], &[&admin_keypair]).await.unwrap();
+}
+
+async fn token_balance(env: &mut FaucetTestEnv, token_account: &Pubkey) -> u64 {
+    let account = env
+        .context
+        .banks_client
+        .get_account(*token_account)
+        .await
+        .unwrap()
+        .unwrap();
+    TokenAccount::unpack(&account.data).unwrap().amount
+}
+
+async fn load_config(env: &mut FaucetTestEnv) -> FaucetConfig {
+    let account = env
+        .context
+        .banks_client
+        .get_account(env.faucet_config_pda)
+        .await
+        .unwrap()
+        .unwrap();
+    FaucetConfig::deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+async fn load_user_record(env: &mut FaucetTestEnv, user: &Pubkey) -> UserClaimedRecord {
+    let (user_claim_pda, _) =
+        Pubkey::find_program_address(&[b"user_claim", user.as_ref()], &env.program_id);
+    let account = env
+        .context
+        .banks_client
+        .get_account(user_claim_pda)
+        .await
+        .unwrap()
+        .unwrap();
+    UserClaimedRecord::deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+//custom program error code of a failed transaction
+fn custom_error(res: Result<(), BanksClientError>) -> Option<u32> {
+    match res {
+        Err(BanksClientError::TransactionError(TransactionError::InstructionError(
+            _,
+            InstructionError::Custom(code),
+        ))) => Some(code),
+        _ => None,
+    }
+}
+
+fn claim_ix(
+    env: &FaucetTestEnv,
+    user: &Pubkey,
+    user_token: &Pubkey,
+    amount: Option<u64>,
+) -> Instruction {
+    let (user_claim_pda, _) =
+        Pubkey::find_program_address(&[b"user_claim", user.as_ref()], &env.program_id);
+
+    Instruction {
+        program_id: env.program_id,
+        accounts: vec![
+            AccountMeta::new(*user, true),
+            AccountMeta::new(user_claim_pda, false),
+            AccountMeta::new(*user_token, false),
+            AccountMeta::new(env.treasury, false),
+            AccountMeta::new(env.faucet_config_pda, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(env.faucet_config_pda, false),
+        ],
+        data: borsh::to_vec(&FaucetInstruction::ClaimTokens {
+            amount,
+            referrer: None,
+        })
+        .unwrap(),
+    }
+}
+
+fn update_config_ix(env: &FaucetTestEnv, update: FaucetInstruction) -> Instruction {
+    Instruction {
+        program_id: env.program_id,
+        accounts: vec![
+            AccountMeta::new(env.admin.pubkey(), true),
+            AccountMeta::new(env.faucet_config_pda, false),
+        ],
+        data: borsh::to_vec(&update).unwrap(),
+    }
+}
+
+//new funded wallet with an empty token account for the faucet mint
+async fn new_user(env: &mut FaucetTestEnv) -> (Keypair, Pubkey) {
+    let user = Keypair::new();
+    fund(env, &user.pubkey(), 100_000_000).await;
+    let user_token = create_token_account(env, &user.pubkey()).await;
+    (user, user_token)
+}
+
+fn deposit_ix(
+    env: &FaucetTestEnv,
+    donor: &Pubkey,
+    donor_token: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let (donor_pda, _) = Pubkey::find_program_address(&[b"donor", donor.as_ref()], &env.program_id);
+
+    Instruction {
+        program_id: env.program_id,
+        accounts: vec![
+            AccountMeta::new(*donor, true),
+            AccountMeta::new(donor_pda, false),
+            AccountMeta::new(*donor_token, false),
+            AccountMeta::new(env.treasury, false),
+            AccountMeta::new(env.faucet_config_pda, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: borsh::to_vec(&FaucetInstruction::Deposit { amount }).unwrap(),
+    }
+}
+
+#[tokio::test]
+// Setup → Two Deposits → Donor record and faucet totals
+async fn test_deposit_tracks_donor() {
+    let mut env = setup_faucet(1_000_000, 60, 0).await;
+
+    let donor = Keypair::new();
+    fund(&mut env, &donor.pubkey(), 100_000_000).await;
+    let donor_token = create_token_account(&mut env, &donor.pubkey()).await;
+    mint_tokens(&mut env, &donor_token, 5_000_000).await;
+
+    let first_ix = deposit_ix(&env, &donor.pubkey(), &donor_token, 2_000_000);
+    send(&mut env, &[first_ix], &[&donor]).await.unwrap();
+    let second_ix = deposit_ix(&env, &donor.pubkey(), &donor_token, 1_000_000);
+    send(&mut env, &[second_ix], &[&donor]).await.unwrap();
+
+    let treasury = env.treasury;
+    assert_eq!(token_balance(&mut env, &treasury).await, 3_000_000);
+    assert_eq!(token_balance(&mut env, &donor_token).await, 2_000_000);
+
+    let (donor_pda, _) =
+        Pubkey::find_program_address(&[b"donor", donor.pubkey().as_ref()], &env.program_id);
+    let donor_account = env
+        .context
+        .banks_client
+        .get_account(donor_pda)
+        .await
+        .unwrap()
+        .unwrap();
+    let contribution = DonorContribution::try_from_slice(&donor_account.data).unwrap();
+    assert_eq!(contribution.donor, donor.pubkey());
+    assert_eq!(contribution.total_deposited, 3_000_000);
+    assert_eq!(contribution.deposit_count, 2);
+
+    let config = load_config(&mut env).await;
+    assert_eq!(config.total_deposited, 3_000_000);
+    assert_eq!(config.total_donors, 1);
+
+    //zero deposits are rejected
+    let zero_ix = deposit_ix(&env, &donor.pubkey(), &donor_token, 0);
+    assert!(send(&mut env, &[zero_ix], &[&donor]).await.is_err());
+
+    //a second account owned by the config PDA is not the treasury
+    let faucet_config_pda = env.faucet_config_pda;
+    let decoy = create_token_account(&mut env, &faucet_config_pda).await;
+    let mut decoy_ix = deposit_ix(&env, &donor.pubkey(), &donor_token, 1_000_000);
+    decoy_ix.accounts[3] = AccountMeta::new(decoy, false);
+    assert!(is_invalid_account_data(
+        send(&mut env, &[decoy_ix], &[&donor]).await
+    ));
+    assert_eq!(token_balance(&mut env, &decoy).await, 0);
+    assert_eq!(load_config(&mut env).await.total_deposited, 3_000_000);
+}
+
+#[tokio::test]
+// Top-up mode → partial claim → claim at target rejected without touching the record
+async fn test_top_up_to_target_claim_mode() {
+    let mut env = setup_faucet(1_000_000, 0, 10_000_000).await;
+
+    let update_ix = update_config_ix(
+        &env,
+        FaucetInstruction::UpdateFaucetConfig(ConfigUpdate {
+            claim_mode: Some(ClaimMode::TopUpToTarget {
+                target_balance: 1_000_000,
+            }),
+            ..Default::default()
+        }),
+    );
+    let admin = env.admin.insecure_clone();
+    send(&mut env, &[update_ix], &[&admin]).await.unwrap();
+
+    let (user, user_token) = new_user(&mut env).await;
+    mint_tokens(&mut env, &user_token, 300_000).await;
+
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    send(&mut env, &[ix], &[&user]).await.unwrap();
+    assert_eq!(token_balance(&mut env, &user_token).await, 1_000_000);
+
+    let record = load_user_record(&mut env, &user.pubkey()).await;
+    assert_eq!(record.total_claims, 1);
+
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    let res = send(&mut env, &[ix], &[&user]).await;
+    assert_eq!(custom_error(res), Some(1005));
+
+    let record_after = load_user_record(&mut env, &user.pubkey()).await;
+    assert_eq!(record_after.total_claims, 1);
+    assert_eq!(record_after.last_claim_time, record.last_claim_time);
+}
+
+#[tokio::test]
+// Partial claim → rest of the allowance → empty bucket rejects the next claim
+async fn test_claim_spends_allowance() {
+    let mut env = setup_faucet(1_000_000, 3600, 10_000_000).await;
+    let (user, user_token) = new_user(&mut env).await;
+
+    //more than tokens_per_claim is never allowed
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, Some(1_000_001));
+    let res = send(&mut env, &[ix], &[&user]).await;
+    assert_eq!(custom_error(res), Some(1004));
+
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, Some(400_000));
+    send(&mut env, &[ix], &[&user]).await.unwrap();
+
+    let record = load_user_record(&mut env, &user.pubkey()).await;
+    assert!(record.allowance >= 600_000 && record.allowance < 1_000_000);
+
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, Some(600_000));
+    send(&mut env, &[ix], &[&user]).await.unwrap();
+    assert_eq!(token_balance(&mut env, &user_token).await, 1_000_000);
+
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    let res = send(&mut env, &[ix], &[&user]).await;
+    assert_eq!(custom_error(res), Some(1000));
+
+    let record = load_user_record(&mut env, &user.pubkey()).await;
+    assert_eq!(record.total_claims, 2);
+}
+
+#[tokio::test]
+// Default bucket → admin raises tokens_per_claim and shortens the cooldown → claims follow
+async fn test_default_bucket_follows_config_updates() {
+    let mut env = setup_faucet(1_000_000, 3600, 10_000_000).await;
+    let admin = env.admin.insecure_clone();
+    let (user, user_token) = new_user(&mut env).await;
+
+    let update_ix = update_config_ix(
+        &env,
+        FaucetInstruction::UpdateFaucetConfig(ConfigUpdate {
+            tokens_per_claim: Some(2_000_000),
+            ..Default::default()
+        }),
+    );
+    send(&mut env, &[update_ix], &[&admin]).await.unwrap();
+
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    send(&mut env, &[ix], &[&user]).await.unwrap();
+    assert_eq!(token_balance(&mut env, &user_token).await, 2_000_000);
+
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    let res = send(&mut env, &[ix], &[&user]).await;
+    assert_eq!(custom_error(res), Some(1000));
+
+    //a shorter cooldown refills the bucket faster
+    let update_ix = update_config_ix(
+        &env,
+        FaucetInstruction::UpdateFaucetConfig(ConfigUpdate {
+            cooldown_seconds: Some(60),
+            ..Default::default()
+        }),
+    );
+    send(&mut env, &[update_ix], &[&admin]).await.unwrap();
+
+    let record = load_user_record(&mut env, &user.pubkey()).await;
+    set_clock(&mut env, record.last_claim_time + 60).await;
+
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    send(&mut env, &[ix], &[&user]).await.unwrap();
+    assert_eq!(token_balance(&mut env, &user_token).await, 4_000_000);
+}
+
+#[tokio::test]
+// Halved emission → half a claim per cooldown, for the default and an explicit bucket
+async fn test_emission_decay_lowers_throughput() {
+    let mut env = setup_faucet(1_000_000, 60, 10_000_000).await;
+    let admin = env.admin.insecure_clone();
+
+    let update_ix = update_config_ix(
+        &env,
+        FaucetInstruction::UpdateFaucetConfig(ConfigUpdate {
+            emission: Some(EmissionCurve::StepHalving {
+                interval_seconds: 1_000,
+            }),
+            ..Default::default()
+        }),
+    );
+    send(&mut env, &[update_ix], &[&admin]).await.unwrap();
+
+    let created_at = load_config(&mut env).await.created_at;
+    set_clock(&mut env, created_at + 1_500).await;
+
+    let (user, user_token) = new_user(&mut env).await;
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    send(&mut env, &[ix], &[&user]).await.unwrap();
+    assert_eq!(token_balance(&mut env, &user_token).await, 500_000);
+
+    //the halved claim used up the whole decayed allowance
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, Some(1));
+    let res = send(&mut env, &[ix], &[&user]).await;
+    assert_eq!(custom_error(res), Some(1000));
+
+    set_clock(&mut env, created_at + 1_560).await;
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    send(&mut env, &[ix], &[&user]).await.unwrap();
+    assert_eq!(token_balance(&mut env, &user_token).await, 1_000_000);
+
+    let update_ix = update_config_ix(
+        &env,
+        FaucetInstruction::UpdateFaucetConfig(ConfigUpdate {
+            rate_limit: Some(RateLimitPolicy::TokenBucket {
+                refill_amount: 1_000_000,
+                refill_interval: 60,
+                burst: 1_000_000,
+            }),
+            ..Default::default()
+        }),
+    );
+    send(&mut env, &[update_ix], &[&admin]).await.unwrap();
+
+    let (user, user_token) = new_user(&mut env).await;
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    send(&mut env, &[ix], &[&user]).await.unwrap();
+    assert_eq!(token_balance(&mut env, &user_token).await, 500_000);
+
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, Some(500_000));
+    let res = send(&mut env, &[ix], &[&user]).await;
+    assert_eq!(custom_error(res), Some(1000));
+}
+
+#[tokio::test]
+// Slot cooldown → immediate retry rejected → warp past the cooldown → claim again
+async fn test_slot_based_cooldown() {
+    let mut env = setup_faucet(1_000_000, 0, 10_000_000).await;
+
+    let update_ix = update_config_ix(
+        &env,
+        FaucetInstruction::UpdateFaucetConfig(ConfigUpdate {
+            cooldown_seconds: Some(50),
+            rate_limit: Some(RateLimitPolicy::FixedCooldown),
+            cooldown_unit: Some(CooldownUnit::Slots),
+            ..Default::default()
+        }),
+    );
+    let admin = env.admin.insecure_clone();
+    send(&mut env, &[update_ix], &[&admin]).await.unwrap();
+
+    let (user, user_token) = new_user(&mut env).await;
+
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    send(&mut env, &[ix], &[&user]).await.unwrap();
+
+    let record = load_user_record(&mut env, &user.pubkey()).await;
+    assert_eq!(record.rate_limit_unit, CooldownUnit::Slots);
+    assert_eq!(record.last_claim_tick, record.last_claim_slot as i64);
+
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    let res = send(&mut env, &[ix], &[&user]).await;
+    assert_eq!(custom_error(res), Some(1000));
+
+    env.context
+        .warp_to_slot(record.last_claim_slot + 100)
+        .unwrap();
+
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    send(&mut env, &[ix], &[&user]).await.unwrap();
+
+    let record_after = load_user_record(&mut env, &user.pubkey()).await;
+    assert_eq!(record_after.total_claims, 2);
+    assert!(record_after.last_claim_slot >= record.last_claim_slot + 100);
+}
+
+#[tokio::test]
+// Claim under a seconds cooldown → switch to slots → the earlier claim still counts
+async fn test_cooldown_unit_switch_keeps_history() {
+    let mut env = setup_faucet(1_000_000, 3600, 10_000_000).await;
+    let admin = env.admin.insecure_clone();
+
+    let update_ix = update_config_ix(
+        &env,
+        FaucetInstruction::UpdateFaucetConfig(ConfigUpdate {
+            rate_limit: Some(RateLimitPolicy::FixedCooldown),
+            ..Default::default()
+        }),
+    );
+    send(&mut env, &[update_ix], &[&admin]).await.unwrap();
+
+    let (user, user_token) = new_user(&mut env).await;
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    send(&mut env, &[ix], &[&user]).await.unwrap();
+    let record = load_user_record(&mut env, &user.pubkey()).await;
+
+    let update_ix = update_config_ix(
+        &env,
+        FaucetInstruction::UpdateFaucetConfig(ConfigUpdate {
+            cooldown_seconds: Some(1_000),
+            cooldown_unit: Some(CooldownUnit::Slots),
+            ..Default::default()
+        }),
+    );
+    send(&mut env, &[update_ix], &[&admin]).await.unwrap();
+
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    let res = send(&mut env, &[ix], &[&user]).await;
+    assert_eq!(custom_error(res), Some(1000));
+
+    //1000 slots of 400ms later, on both clocks
+    env.context
+        .warp_to_slot(record.last_claim_slot + 1_000)
+        .unwrap();
+    set_clock(&mut env, record.last_claim_time + 400).await;
+
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    send(&mut env, &[ix], &[&user]).await.unwrap();
+
+    let record_after = load_user_record(&mut env, &user.pubkey()).await;
+    assert_eq!(record_after.total_claims, 2);
+    assert_eq!(record_after.rate_limit_unit, CooldownUnit::Slots);
+}
+
+async fn set_clock(env: &mut FaucetTestEnv, unix_timestamp: i64) {
+    let mut clock: Clock = env.context.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp = unix_timestamp;
+    env.context.set_sysvar(&clock);
+}
+
+#[tokio::test]
+// Schedule → claim before start / after end rejected → crank deactivates
+async fn test_claim_schedule() {
+    let mut env = setup_faucet(1_000_000, 0, 10_000_000).await;
+    let now: Clock = env.context.banks_client.get_sysvar().await.unwrap();
+    let start = now.unix_timestamp + 1_000;
+    let end = start + 1_000;
+
+    let update_ix = update_config_ix(
+        &env,
+        FaucetInstruction::UpdateFaucetConfig(ConfigUpdate {
+            starts_at: Some(Some(start)),
+            ends_at: Some(Some(end)),
+            ..Default::default()
+        }),
+    );
+    let admin = env.admin.insecure_clone();
+    send(&mut env, &[update_ix], &[&admin]).await.unwrap();
+
+    let crank_ix = Instruction {
+        program_id: env.program_id,
+        accounts: vec![AccountMeta::new(env.faucet_config_pda, false)],
+        data: borsh::to_vec(&FaucetInstruction::CloseExpiredFaucet).unwrap(),
+    };
+
+    let (user, user_token) = new_user(&mut env).await;
+
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    let res = send(&mut env, &[ix], &[&user]).await;
+    assert_eq!(custom_error(res), Some(1007));
+
+    //cranking before the end does nothing
+    let res = send(&mut env, std::slice::from_ref(&crank_ix), &[]).await;
+    assert_eq!(custom_error(res), Some(1009));
+
+    set_clock(&mut env, start + 10).await;
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    send(&mut env, &[ix], &[&user]).await.unwrap();
+
+    set_clock(&mut env, end).await;
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    let res = send(&mut env, &[ix], &[&user]).await;
+    assert_eq!(custom_error(res), Some(1008));
+
+    send(&mut env, &[crank_ix], &[]).await.unwrap();
+    assert!(load_config(&mut env).await.is_paused(PAUSE_CLAIMS));
+}
+
+#[tokio::test]
+// Low water mark with auto pause → claim drains below it → faucet deactivated
+async fn test_low_water_mark_pauses_faucet() {
+    let mut env = setup_faucet(1_000_000, 0, 2_500_000).await;
+
+    let update_ix = update_config_ix(
+        &env,
+        FaucetInstruction::UpdateFaucetConfig(ConfigUpdate {
+            low_water_mark: Some(Some(1_000_000)),
+            pause_on_low_water: Some(true),
+            ..Default::default()
+        }),
+    );
+    let admin = env.admin.insecure_clone();
+    send(&mut env, &[update_ix], &[&admin]).await.unwrap();
+
+    let (user, user_token) = new_user(&mut env).await;
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    send(&mut env, &[ix], &[&user]).await.unwrap();
+    assert!(!load_config(&mut env).await.is_paused(PAUSE_CLAIMS));
+
+    //1.5M -> 0.5M crosses the mark
+    let (other, other_token) = new_user(&mut env).await;
+    let ix = claim_ix(&env, &other.pubkey(), &other_token, None);
+    send(&mut env, &[ix], &[&other]).await.unwrap();
+    assert!(load_config(&mut env).await.is_paused(PAUSE_CLAIMS));
+
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    let res = send(&mut env, &[ix], &[&user]).await;
+    assert_eq!(custom_error(res), Some(1001));
+}
+
+fn pause_ix(env: &FaucetTestEnv, instruction: FaucetInstruction) -> Instruction {
+    let (pause_log_pda, _) = Pubkey::find_program_address(&[b"pause_log"], &env.program_id);
+
+    Instruction {
+        program_id: env.program_id,
+        accounts: vec![
+            AccountMeta::new(env.admin.pubkey(), true),
+            AccountMeta::new(env.faucet_config_pda, false),
+            AccountMeta::new(pause_log_pda, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: borsh::to_vec(&instruction).unwrap(),
+    }
+}
+
+#[tokio::test]
+// Pause / Resume cycles → faucet status follows → log keeps the latest entries in order
+async fn test_pause_resume_history() {
+    let mut env = setup_faucet(1_000_000, 0, 10_000_000).await;
+    let admin = env.admin.insecure_clone();
+
+    for round in 0..6u16 {
+        let ix = pause_ix(
+            &env,
+            FaucetInstruction::EmergencyPause {
+                flags: PAUSE_ALL,
+                reason: round,
+                memo: Some(format!("incident {}", round)),
+            },
+        );
+        send(&mut env, &[ix], &[&admin]).await.unwrap();
+        assert!(load_config(&mut env).await.is_paused(PAUSE_CLAIMS));
+
+        let ix = pause_ix(
+            &env,
+            FaucetInstruction::Resume {
+                flags: PAUSE_ALL,
+                reason: 100 + round,
+                memo: None,
+            },
+        );
+        send(&mut env, &[ix], &[&admin]).await.unwrap();
+        assert!(!load_config(&mut env).await.is_paused(PAUSE_CLAIMS));
+    }
+
+    let (pause_log_pda, _) = Pubkey::find_program_address(&[b"pause_log"], &env.program_id);
+    let account = env
+        .context
+        .banks_client
+        .get_account(pause_log_pda)
+        .await
+        .unwrap()
+        .unwrap();
+    let history = PauseLog::try_from_slice(&account.data).unwrap().history();
+
+    //12 events, only the last 8 are kept
+    assert_eq!(history.len(), 8);
+    assert_eq!(history[0].action, PauseAction::Pause);
+    assert_eq!(history[0].reason, 2);
+    assert!(history[0].memo.starts_with(b"incident 2"));
+    assert_eq!(history[7].action, PauseAction::Resume);
+    assert_eq!(history[7].reason, 105);
+
+    let ix = pause_ix(
+        &env,
+        FaucetInstruction::EmergencyPause {
+            flags: PAUSE_ALL,
+            reason: 1,
+            memo: Some("x".repeat(33)),
+        },
+    );
+    let res = send(&mut env, &[ix], &[&admin]).await;
+    assert_eq!(custom_error(res), Some(1011));
+}
+
+//program owned copy of the real config at a random address, naming someone else as admin
+async fn forge_config(env: &mut FaucetTestEnv, admin: &Pubkey) -> Pubkey {
+    let mut config = load_config(env).await;
+    config.admin = *admin;
+    let forged = Pubkey::new_unique();
+    set_program_account(env, &forged, &config).await;
+    forged
+}
+
+fn is_invalid_account_data(res: Result<(), BanksClientError>) -> bool {
+    matches!(
+        res,
+        Err(BanksClientError::TransactionError(
+            TransactionError::InstructionError(_, InstructionError::InvalidAccountData)
+        ))
+    )
+}
+
+#[tokio::test]
+// Forged config naming an attacker as admin → pause and resume both rejected
+async fn test_pause_rejects_forged_config() {
+    let mut env = setup_faucet(1_000_000, 0, 10_000_000).await;
+    let admin = env.admin.insecure_clone();
+    let attacker = Keypair::new();
+    fund(&mut env, &attacker.pubkey(), 1_000_000_000).await;
+    let forged = forge_config(&mut env, &attacker.pubkey()).await;
+
+    let mut ix = pause_ix(
+        &env,
+        FaucetInstruction::EmergencyPause {
+            flags: PAUSE_ALL,
+            reason: 1,
+            memo: None,
+        },
+    );
+    ix.accounts[0] = AccountMeta::new(attacker.pubkey(), true);
+    ix.accounts[1] = AccountMeta::new(forged, false);
+    let res = send(&mut env, &[ix], &[&attacker]).await;
+    assert!(is_invalid_account_data(res));
+
+    let ix = pause_ix(
+        &env,
+        FaucetInstruction::EmergencyPause {
+            flags: PAUSE_ALL,
+            reason: 1,
+            memo: None,
+        },
+    );
+    send(&mut env, &[ix], &[&admin]).await.unwrap();
+
+    let mut ix = pause_ix(
+        &env,
+        FaucetInstruction::Resume {
+            flags: PAUSE_ALL,
+            reason: 2,
+            memo: None,
+        },
+    );
+    ix.accounts[0] = AccountMeta::new(attacker.pubkey(), true);
+    ix.accounts[1] = AccountMeta::new(forged, false);
+    let res = send(&mut env, &[ix], &[&attacker]).await;
+    assert!(is_invalid_account_data(res));
+    assert!(load_config(&mut env).await.is_paused(PAUSE_CLAIMS));
+}
+
+#[tokio::test]
+// Pause claims only → deposits still work → pause config updates → resume
+async fn test_granular_pause_flags() {
+    let mut env = setup_faucet(1_000_000, 0, 10_000_000).await;
+    let admin = env.admin.insecure_clone();
+
+    let ix = pause_ix(
+        &env,
+        FaucetInstruction::EmergencyPause {
+            flags: PAUSE_CLAIMS | PAUSE_CONFIG_UPDATES,
+            reason: 7,
+            memo: None,
+        },
+    );
+    send(&mut env, &[ix], &[&admin]).await.unwrap();
+
+    let (user, user_token) = new_user(&mut env).await;
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    let res = send(&mut env, &[ix], &[&user]).await;
+    assert_eq!(custom_error(res), Some(1001));
+
+    //deposits are unaffected
+    mint_tokens(&mut env, &user_token, 500_000).await;
+    let ix = deposit_ix(&env, &user.pubkey(), &user_token, 500_000);
+    send(&mut env, &[ix], &[&user]).await.unwrap();
+
+    let update_ix = update_config_ix(
+        &env,
+        FaucetInstruction::UpdateFaucetConfig(ConfigUpdate {
+            tokens_per_claim: Some(5),
+            ..Default::default()
+        }),
+    );
+    let res = send(&mut env, std::slice::from_ref(&update_ix), &[&admin]).await;
+    assert_eq!(custom_error(res), Some(1012));
+
+    let ix = pause_ix(
+        &env,
+        FaucetInstruction::Resume {
+            flags: PAUSE_CONFIG_UPDATES,
+            reason: 8,
+            memo: None,
+        },
+    );
+    send(&mut env, &[ix], &[&admin]).await.unwrap();
+    send(&mut env, &[update_ix], &[&admin]).await.unwrap();
+
+    //now stop deposits instead of claims
+    let ix = pause_ix(
+        &env,
+        FaucetInstruction::EmergencyPause {
+            flags: PAUSE_DEPOSITS,
+            reason: 9,
+            memo: None,
+        },
+    );
+    send(&mut env, &[ix], &[&admin]).await.unwrap();
+    let config = load_config(&mut env).await;
+    assert_eq!(config.paused, PAUSE_CLAIMS | PAUSE_DEPOSITS);
+
+    mint_tokens(&mut env, &user_token, 1).await;
+    let ix = deposit_ix(&env, &user.pubkey(), &user_token, 1);
+    let res = send(&mut env, &[ix], &[&user]).await;
+    assert_eq!(custom_error(res), Some(1012));
+}
+
+fn distribute_ix(
+    env: &FaucetTestEnv,
+    distributor: &Pubkey,
+    recipient: &Pubkey,
+    recipient_token: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let (distributor_pda, _) =
+        Pubkey::find_program_address(&[b"distributor", distributor.as_ref()], &env.program_id);
+
+    Instruction {
+        program_id: env.program_id,
+        accounts: vec![
+            AccountMeta::new(*distributor, true),
+            AccountMeta::new(distributor_pda, false),
+            AccountMeta::new(*recipient_token, false),
+            AccountMeta::new(env.treasury, false),
+            AccountMeta::new_readonly(env.faucet_config_pda, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: borsh::to_vec(&FaucetInstruction::Distribute {
+            recipient: *recipient,
+            amount,
+        })
+        .unwrap(),
+    }
+}
+
+#[tokio::test]
+// Grant → distributor sends within allowance → over allowance rejected → expired grant rejected
+async fn test_distributor_allowance() {
+    let mut env = setup_faucet(1_000_000, 3600, 10_000_000).await;
+    let admin = env.admin.insecure_clone();
+
+    let distributor = Keypair::new();
+    fund(&mut env, &distributor.pubkey(), 100_000_000).await;
+    let (distributor_pda, _) = Pubkey::find_program_address(
+        &[b"distributor", distributor.pubkey().as_ref()],
+        &env.program_id,
+    );
+
+    let grant = |env: &FaucetTestEnv, amount: u64, expires_at: Option<i64>| Instruction {
+        program_id: env.program_id,
+        accounts: vec![
+            AccountMeta::new(env.admin.pubkey(), true),
+            AccountMeta::new_readonly(env.faucet_config_pda, false),
+            AccountMeta::new(distributor_pda, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: borsh::to_vec(&FaucetInstruction::GrantAllowance {
+            distributor: distributor.pubkey(),
+            amount,
+            expires_at,
+        })
+        .unwrap(),
+    };
+
+    let ix = grant(&env, 3_000_000, None);
+    send(&mut env, &[ix], &[&admin]).await.unwrap();
+
+    let (member, member_token) = new_user(&mut env).await;
+
+    //twice in a row, no claim cooldown involved
+    for _ in 0..2 {
+        let ix = distribute_ix(
+            &env,
+            &distributor.pubkey(),
+            &member.pubkey(),
+            &member_token,
+            1_000_000,
+        );
+        send(&mut env, &[ix], &[&distributor]).await.unwrap();
+    }
+    assert_eq!(token_balance(&mut env, &member_token).await, 2_000_000);
+
+    let ix = distribute_ix(
+        &env,
+        &distributor.pubkey(),
+        &member.pubkey(),
+        &member_token,
+        1_000_001,
+    );
+    let res = send(&mut env, &[ix], &[&distributor]).await;
+    assert_eq!(custom_error(res), Some(1014));
+
+    let account = env
+        .context
+        .banks_client
+        .get_account(distributor_pda)
+        .await
+        .unwrap()
+        .unwrap();
+    let allowance = DistributorAllowance::deserialize(&mut account.data.as_slice()).unwrap();
+    assert_eq!(allowance.remaining, 1_000_000);
+    assert_eq!(allowance.total_distributed, 2_000_000);
+
+    //regular claims keep their own cooldown record
+    let ix = claim_ix(&env, &member.pubkey(), &member_token, None);
+    send(&mut env, &[ix], &[&member]).await.unwrap();
+
+    let ix = grant(&env, 5_000_000, Some(1));
+    send(&mut env, &[ix], &[&admin]).await.unwrap();
+    let ix = distribute_ix(
+        &env,
+        &distributor.pubkey(),
+        &member.pubkey(),
+        &member_token,
+        1,
+    );
+    let res = send(&mut env, &[ix], &[&distributor]).await;
+    assert_eq!(custom_error(res), Some(1013));
+}
+
+#[test]
+fn test_airdrop_chunker_splits_recipients() {
+    let program_id = Pubkey::new_unique();
+    let recipients: Vec<(Pubkey, u64)> = (0..45).map(|i| (Pubkey::new_unique(), i)).collect();
+
+    let instructions = airdrop_instructions(
+        &program_id,
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &recipients,
+    );
+
+    assert_eq!(instructions.len(), 3);
+    let mut seen = vec![];
+    for ix in &instructions {
+        let FaucetInstruction::Airdrop { amounts } =
+            FaucetInstruction::try_from_slice(&ix.data).unwrap()
+        else {
+            panic!("expected an Airdrop instruction");
+        };
+        assert!(amounts.len() <= MAX_AIRDROP_RECIPIENTS);
+        assert_eq!(ix.accounts.len(), 4 + amounts.len());
+        seen.extend(ix.accounts[4..].iter().map(|meta| meta.pubkey).zip(amounts));
+    }
+    assert_eq!(seen, recipients);
+}
+
+#[tokio::test]
+// Forged config naming the attacker as admin → self granted allowance rejected, nothing to distribute
+async fn test_grant_rejects_forged_config() {
+    let mut env = setup_faucet(1_000_000, 3600, 10_000_000).await;
+
+    let attacker = Keypair::new();
+    fund(&mut env, &attacker.pubkey(), 100_000_000).await;
+    let forged = forge_config(&mut env, &attacker.pubkey()).await;
+    let (distributor_pda, _) = Pubkey::find_program_address(
+        &[b"distributor", attacker.pubkey().as_ref()],
+        &env.program_id,
+    );
+
+    let ix = Instruction {
+        program_id: env.program_id,
+        accounts: vec![
+            AccountMeta::new(attacker.pubkey(), true),
+            AccountMeta::new_readonly(forged, false),
+            AccountMeta::new(distributor_pda, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: borsh::to_vec(&FaucetInstruction::GrantAllowance {
+            distributor: attacker.pubkey(),
+            amount: 5_000_000,
+            expires_at: None,
+        })
+        .unwrap(),
+    };
+    let res = send(&mut env, &[ix], &[&attacker]).await;
+    assert!(is_invalid_account_data(res));
+
+    let attacker_token = create_token_account(&mut env, &attacker.pubkey()).await;
+    let ix = distribute_ix(
+        &env,
+        &attacker.pubkey(),
+        &attacker.pubkey(),
+        &attacker_token,
+        5_000_000,
+    );
+    assert!(send(&mut env, &[ix], &[&attacker]).await.is_err());
+    let treasury = env.treasury;
+    assert_eq!(token_balance(&mut env, &treasury).await, 10_000_000);
+}
+
+#[tokio::test]
+// Second token account owned by the config PDA → claims, airdrops and withdrawals can't pay from it
+async fn test_payouts_use_the_pinned_treasury() {
+    let mut env = setup_faucet(1_000_000, 0, 10_000_000).await;
+    let admin = env.admin.insecure_clone();
+    assert_eq!(load_config(&mut env).await.treasury, env.treasury);
+
+    let faucet_config_pda = env.faucet_config_pda;
+    let decoy = create_token_account(&mut env, &faucet_config_pda).await;
+    mint_tokens(&mut env, &decoy, 5_000_000).await;
+
+    let (user, user_token) = new_user(&mut env).await;
+    let mut ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    ix.accounts[3] = AccountMeta::new(decoy, false);
+    assert!(is_invalid_account_data(
+        send(&mut env, &[ix], &[&user]).await
+    ));
+
+    let instructions = airdrop_instructions(
+        &env.program_id,
+        &env.admin.pubkey(),
+        &decoy,
+        &[(user_token, 1_000_000)],
+    );
+    assert!(is_invalid_account_data(
+        send(&mut env, &instructions, &[&admin]).await
+    ));
+
+    let admin_token = create_token_account(&mut env, &admin.pubkey()).await;
+    let withdraw_ix = Instruction {
+        program_id: env.program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(admin.pubkey(), true),
+            AccountMeta::new_readonly(faucet_config_pda, false),
+            AccountMeta::new_readonly(faucet_config_pda, false),
+            AccountMeta::new(decoy, false),
+            AccountMeta::new(admin_token, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: borsh::to_vec(&FaucetInstruction::WithdrawTreasury { amount: 1_000_000 }).unwrap(),
+    };
+    assert!(is_invalid_account_data(
+        send(&mut env, &[withdraw_ix], &[&admin]).await
+    ));
+
+    assert_eq!(token_balance(&mut env, &decoy).await, 5_000_000);
+    assert_eq!(token_balance(&mut env, &user_token).await, 0);
+}
+
+#[tokio::test]
+// Airdrop to a cohort → bad recipient skipped → stats updated
+async fn test_airdrop_skips_bad_recipients() {
+    let mut env = setup_faucet(1_000_000, 60, 10_000_000).await;
+    let admin = env.admin.insecure_clone();
+
+    let (_alice, alice_token) = new_user(&mut env).await;
+    let (_bob, bob_token) = new_user(&mut env).await;
+    let not_a_token_account = Keypair::new().pubkey();
+    fund(&mut env, &not_a_token_account, 1_000_000).await;
+
+    let instructions = airdrop_instructions(
+        &env.program_id,
+        &env.admin.pubkey(),
+        &env.treasury,
+        &[
+            (alice_token, 2_000_000),
+            (not_a_token_account, 1_000_000),
+            (bob_token, 3_000_000),
+        ],
+    );
+    send(&mut env, &instructions, &[&admin]).await.unwrap();
+
+    assert_eq!(token_balance(&mut env, &alice_token).await, 2_000_000);
+    assert_eq!(token_balance(&mut env, &bob_token).await, 3_000_000);
+
+    let config = load_config(&mut env).await;
+    assert_eq!(config.total_airdropped, 5_000_000);
+    assert_eq!(config.total_airdrop_recipients, 2);
+
+    //treasury has 5M left, the second recipient can't be covered
+    let instructions = airdrop_instructions(
+        &env.program_id,
+        &env.admin.pubkey(),
+        &env.treasury,
+        &[(alice_token, 4_000_000), (bob_token, 4_000_000)],
+    );
+    send(&mut env, &instructions, &[&admin]).await.unwrap();
+    assert_eq!(token_balance(&mut env, &alice_token).await, 6_000_000);
+    assert_eq!(token_balance(&mut env, &bob_token).await, 3_000_000);
+}
+
+#[tokio::test]
+// Frozen recipient and a token account copy owned by another program → both skipped, the rest paid
+async fn test_airdrop_skips_frozen_recipients() {
+    let mut env = setup_faucet(1_000_000, 60, 10_000_000).await;
+    let admin = env.admin.insecure_clone();
+
+    let (_alice, alice_token) = new_user(&mut env).await;
+    let (_bob, bob_token) = new_user(&mut env).await;
+
+    let mut frozen = env
+        .context
+        .banks_client
+        .get_account(bob_token)
+        .await
+        .unwrap()
+        .unwrap();
+    let mut token_account = TokenAccount::unpack(&frozen.data).unwrap();
+    token_account.state = AccountState::Frozen;
+    TokenAccount::pack(token_account, &mut frozen.data).unwrap();
+    env.context
+        .set_account(&bob_token, &AccountSharedData::from(frozen.clone()));
+
+    //same bytes, but not a real token account
+    let impostor = Pubkey::new_unique();
+    let mut impostor_account = frozen;
+    let mut token_account = TokenAccount::unpack(&impostor_account.data).unwrap();
+    token_account.state = AccountState::Initialized;
+    TokenAccount::pack(token_account, &mut impostor_account.data).unwrap();
+    impostor_account.owner = env.program_id;
+    env.context
+        .set_account(&impostor, &AccountSharedData::from(impostor_account));
+
+    let instructions = airdrop_instructions(
+        &env.program_id,
+        &env.admin.pubkey(),
+        &env.treasury,
+        &[
+            (bob_token, 1_000_000),
+            (impostor, 1_000_000),
+            (alice_token, 2_000_000),
+        ],
+    );
+    send(&mut env, &instructions, &[&admin]).await.unwrap();
+
+    assert_eq!(token_balance(&mut env, &alice_token).await, 2_000_000);
+    assert_eq!(token_balance(&mut env, &bob_token).await, 0);
+
+    let config = load_config(&mut env).await;
+    assert_eq!(config.total_airdropped, 2_000_000);
+    assert_eq!(config.total_airdrop_recipients, 1);
+}
+
+#[tokio::test]
+// Wallet with no SOL claims with a sponsor → sponsor paid rent → close refunds the sponsor
+async fn test_sponsored_claim() {
+    let mut env = setup_faucet(1_000_000, 0, 10_000_000).await;
+
+    //never funded, only signs
+    let user = Keypair::new();
+    let user_token = create_token_account(&mut env, &user.pubkey()).await;
+
+    let sponsor = Keypair::new();
+    fund(&mut env, &sponsor.pubkey(), 100_000_000).await;
+
+    let mut ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    ix.accounts[0].is_writable = false;
+    ix.accounts.push(AccountMeta::new(sponsor.pubkey(), true));
+    send(&mut env, &[ix], &[&user, &sponsor]).await.unwrap();
+
+    assert_eq!(token_balance(&mut env, &user_token).await, 1_000_000);
+    let record = load_user_record(&mut env, &user.pubkey()).await;
+    assert_eq!(record.rent_payer, sponsor.pubkey());
+
+    let sponsor_before = env
+        .context
+        .banks_client
+        .get_balance(sponsor.pubkey())
+        .await
+        .unwrap();
+
+    let (user_claim_pda, _) =
+        Pubkey::find_program_address(&[b"user_claim", user.pubkey().as_ref()], &env.program_id);
+    let (program_id, faucet_config_pda) = (env.program_id, env.faucet_config_pda);
+    let close_ix = |rent_receiver: Pubkey| Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(user.pubkey(), true),
+            AccountMeta::new(user_claim_pda, false),
+            AccountMeta::new(rent_receiver, false),
+            AccountMeta::new_readonly(faucet_config_pda, false),
+        ],
+        data: borsh::to_vec(&FaucetInstruction::CloseClaimRecord).unwrap(),
+    };
+
+    //rent can't be redirected to the user
+    let ix = close_ix(user.pubkey());
+    assert!(send(&mut env, &[ix], &[&user]).await.is_err());
+
+    let ix = close_ix(sponsor.pubkey());
+    send(&mut env, &[ix], &[&user]).await.unwrap();
+
+    let sponsor_after = env
+        .context
+        .banks_client
+        .get_balance(sponsor.pubkey())
+        .await
+        .unwrap();
+    assert!(sponsor_after > sponsor_before);
+    assert!(
+        env.context
+            .banks_client
+            .get_account(user_claim_pda)
+            .await
+            .unwrap()
+            .is_none()
+    );
+}
+
+//Ed25519 check signed by authority followed by the voucher claim
+fn voucher_ixs(
+    env: &FaucetTestEnv,
+    authority: &Keypair,
+    user: &Pubkey,
+    user_token: &Pubkey,
+    voucher: ClaimVoucher,
+) -> Vec<Instruction> {
+    let message = voucher.message();
+    let signature = authority.sign_message(&message);
+    let signature_ix = new_ed25519_instruction_with_signature(
+        &message,
+        signature.as_array(),
+        &authority.pubkey().to_bytes(),
+    );
+
+    let (used_voucher_pda, _) = Pubkey::find_program_address(
+        &[b"voucher", user.as_ref(), &voucher.nonce.to_le_bytes()],
+        &env.program_id,
+    );
+
+    let (user_claim_pda, _) =
+        Pubkey::find_program_address(&[b"user_claim", user.as_ref()], &env.program_id);
+
+    let claim_ix = Instruction {
+        program_id: env.program_id,
+        accounts: vec![
+            AccountMeta::new(*user, true),
+            AccountMeta::new(used_voucher_pda, false),
+            AccountMeta::new(*user_token, false),
+            AccountMeta::new(env.treasury, false),
+            AccountMeta::new(env.faucet_config_pda, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(solana_program::sysvar::instructions::id(), false),
+            AccountMeta::new_readonly(user_claim_pda, false),
+        ],
+        data: borsh::to_vec(&FaucetInstruction::ClaimWithVoucher {
+            amount: voucher.amount,
+            nonce: voucher.nonce,
+            expiry: voucher.expiry,
+        })
+        .unwrap(),
+    };
+
+    vec![signature_ix, claim_ix]
+}
+
+#[tokio::test]
+// Voucher signed by the authority pays out once → replays, forgeries and expired vouchers fail
+async fn test_claim_with_voucher() {
+    let mut env = setup_faucet(1_000_000, 0, 10_000_000).await;
+    set_clock(&mut env, 1_000).await;
+
+    let authority = Keypair::new();
+    let update_ix = update_config_ix(
+        &env,
+        FaucetInstruction::UpdateFaucetConfig(ConfigUpdate {
+            voucher_authority: Some(Some(authority.pubkey())),
+            ..Default::default()
+        }),
+    );
+    let admin = env.admin.insecure_clone();
+    send(&mut env, &[update_ix], &[&admin]).await.unwrap();
+
+    let (user, user_token) = new_user(&mut env).await;
+    let voucher = ClaimVoucher {
+        faucet: env.faucet_config_pda,
+        user: user.pubkey(),
+        amount: 3_000_000,
+        nonce: 7,
+        expiry: 2_000,
+    };
+
+    let ixs = voucher_ixs(
+        &env,
+        &authority,
+        &user.pubkey(),
+        &user_token,
+        voucher.clone(),
+    );
+    send(&mut env, &ixs, &[&user]).await.unwrap();
+    assert_eq!(token_balance(&mut env, &user_token).await, 3_000_000);
+
+    //same nonce again
+    let ixs = voucher_ixs(
+        &env,
+        &authority,
+        &user.pubkey(),
+        &user_token,
+        voucher.clone(),
+    );
+    assert_eq!(
+        custom_error(send(&mut env, &ixs, &[&user]).await),
+        Some(1019)
+    );
+
+    //signed by someone else
+    let forger = Keypair::new();
+    let forged = ClaimVoucher {
+        nonce: 8,
+        ..voucher.clone()
+    };
+    let ixs = voucher_ixs(&env, &forger, &user.pubkey(), &user_token, forged);
+    assert_eq!(
+        custom_error(send(&mut env, &ixs, &[&user]).await),
+        Some(1017)
+    );
+
+    //signature doesn't cover a bumped amount
+    let mut ixs = voucher_ixs(
+        &env,
+        &authority,
+        &user.pubkey(),
+        &user_token,
+        ClaimVoucher {
+            nonce: 9,
+            ..voucher.clone()
+        },
+    );
+    ixs[1].data = borsh::to_vec(&FaucetInstruction::ClaimWithVoucher {
+        amount: 9_000_000,
+        nonce: 9,
+        expiry: 2_000,
+    })
+    .unwrap();
+    assert_eq!(
+        custom_error(send(&mut env, &ixs, &[&user]).await),
+        Some(1017)
+    );
+
+    //claim without the Ed25519 instruction
+    let ixs = voucher_ixs(
+        &env,
+        &authority,
+        &user.pubkey(),
+        &user_token,
+        ClaimVoucher {
+            nonce: 10,
+            ..voucher.clone()
+        },
+    );
+    assert_eq!(
+        custom_error(send(&mut env, &ixs[1..], &[&user]).await),
+        Some(1017)
+    );
+
+    set_clock(&mut env, 2_000).await;
+    let ixs = voucher_ixs(
+        &env,
+        &authority,
+        &user.pubkey(),
+        &user_token,
+        ClaimVoucher {
+            nonce: 11,
+            ..voucher
+        },
+    );
+    assert_eq!(
+        custom_error(send(&mut env, &ixs, &[&user]).await),
+        Some(1018)
+    );
+    assert_eq!(token_balance(&mut env, &user_token).await, 3_000_000);
+}
+
+//same claim wrapped by the relay program
+fn relayed_claim_ix(env: &FaucetTestEnv, claim: Instruction) -> Instruction {
+    let mut accounts = vec![AccountMeta::new_readonly(env.program_id, false)];
+    accounts.extend(claim.accounts);
+    Instruction {
+        program_id: RELAY_PROGRAM_ID,
+        accounts,
+        data: claim.data,
+    }
+}
+
+#[tokio::test]
+// Anti bot on → claims need the instructions sysvar, CPI and bundled claims are rejected
+async fn test_anti_bot_claims() {
+    let mut env = setup_faucet(1_000_000, 0, 10_000_000).await;
+    let instructions_meta =
+        AccountMeta::new_readonly(solana_program::sysvar::instructions::id(), false);
+
+    //relaying works while the checks are off
+    let (user, user_token) = new_user(&mut env).await;
+    let mut claim = claim_ix(&env, &user.pubkey(), &user_token, Some(100));
+    claim.accounts.push(instructions_meta.clone());
+    let ix = relayed_claim_ix(&env, claim);
+    send(&mut env, &[ix], &[&user]).await.unwrap();
+
+    let update_ix = update_config_ix(
+        &env,
+        FaucetInstruction::UpdateFaucetConfig(ConfigUpdate {
+            anti_bot: Some(true),
+            ..Default::default()
+        }),
+    );
+    let admin = env.admin.insecure_clone();
+    send(&mut env, &[update_ix], &[&admin]).await.unwrap();
+
+    let mut claim = claim_ix(&env, &user.pubkey(), &user_token, Some(100));
+    claim.accounts.push(instructions_meta.clone());
+    let ix = relayed_claim_ix(&env, claim);
+    assert_eq!(
+        custom_error(send(&mut env, &[ix], &[&user]).await),
+        Some(1020)
+    );
+
+    //two users claiming in one transaction
+    let (other, other_token) = new_user(&mut env).await;
+    let mut first = claim_ix(&env, &user.pubkey(), &user_token, Some(100));
+    first.accounts.push(instructions_meta.clone());
+    let mut second = claim_ix(&env, &other.pubkey(), &other_token, Some(100));
+    second.accounts.push(instructions_meta.clone());
+    let res = send(&mut env, &[first, second], &[&user, &other]).await;
+    assert_eq!(custom_error(res), Some(1021));
+
+    //sysvar is mandatory once the checks are on
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, Some(100));
+    assert!(send(&mut env, &[ix], &[&user]).await.is_err());
+
+    let mut ix = claim_ix(&env, &user.pubkey(), &user_token, Some(100));
+    ix.accounts.push(instructions_meta);
+    send(&mut env, &[ix], &[&user]).await.unwrap();
+    assert_eq!(token_balance(&mut env, &user_token).await, 200);
+    assert_eq!(token_balance(&mut env, &other_token).await, 0);
+}
+
+#[tokio::test]
+// Token gate set → claims need the pass token account with enough balance
+async fn test_token_gated_claims() {
+    let mut env = setup_faucet(1_000_000, 0, 10_000_000).await;
+    let pass_mint = create_mint(&mut env).await;
+
+    let update_ix = update_config_ix(
+        &env,
+        FaucetInstruction::UpdateFaucetConfig(ConfigUpdate {
+            token_gate: Some(Some(TokenGate {
+                mint: pass_mint,
+                min_balance: 1,
+            })),
+            ..Default::default()
+        }),
+    );
+    let admin = env.admin.insecure_clone();
+    send(&mut env, &[update_ix], &[&admin]).await.unwrap();
+
+    let (user, user_token) = new_user(&mut env).await;
+    let pass_token = create_token_account_for_mint(&mut env, &pass_mint, &user.pubkey()).await;
+
+    //gate account missing
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    assert!(send(&mut env, &[ix], &[&user]).await.is_err());
+
+    //faucet tokens don't count as a pass
+    let mut ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    ix.accounts
+        .push(AccountMeta::new_readonly(user_token, false));
+    assert!(send(&mut env, &[ix], &[&user]).await.is_err());
+
+    let mut ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    ix.accounts
+        .push(AccountMeta::new_readonly(pass_token, false));
+    assert_eq!(
+        custom_error(send(&mut env, &[ix], &[&user]).await),
+        Some(1022)
+    );
+
+    //someone else's pass
+    let (holder, _) = new_user(&mut env).await;
+    let holder_pass = create_token_account_for_mint(&mut env, &pass_mint, &holder.pubkey()).await;
+    mint_tokens_of(&mut env, &pass_mint, &holder_pass, 1).await;
+    let mut ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    ix.accounts
+        .push(AccountMeta::new_readonly(holder_pass, false));
+    assert!(send(&mut env, &[ix], &[&user]).await.is_err());
+
+    mint_tokens_of(&mut env, &pass_mint, &pass_token, 1).await;
+    let mut ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    ix.accounts
+        .push(AccountMeta::new_readonly(pass_token, false));
+    send(&mut env, &[ix], &[&user]).await.unwrap();
+    assert_eq!(token_balance(&mut env, &user_token).await, 1_000_000);
+}
+
+#[tokio::test]
+// Balance caps set → wallets above either cap are refused, poorer ones can claim
+async fn test_poverty_check() {
+    let mut env = setup_faucet(1_000_000, 0, 10_000_000).await;
+
+    let update_ix = update_config_ix(
+        &env,
+        FaucetInstruction::UpdateFaucetConfig(ConfigUpdate {
+            max_recipient_token_balance: Some(Some(500_000)),
+            max_recipient_lamports: Some(Some(1_000_000_000)),
+            ..Default::default()
+        }),
+    );
+    let admin = env.admin.insecure_clone();
+    send(&mut env, &[update_ix], &[&admin]).await.unwrap();
+
+    //already holds more than the cap
+    let (whale, whale_token) = new_user(&mut env).await;
+    mint_tokens(&mut env, &whale_token, 600_000).await;
+    let ix = claim_ix(&env, &whale.pubkey(), &whale_token, None);
+    assert_eq!(
+        custom_error(send(&mut env, &[ix], &[&whale]).await),
+        Some(1023)
+    );
+
+    //too much SOL
+    let (rich, rich_token) = new_user(&mut env).await;
+    fund(&mut env, &rich.pubkey(), 2_000_000_000).await;
+    let ix = claim_ix(&env, &rich.pubkey(), &rich_token, None);
+    assert_eq!(
+        custom_error(send(&mut env, &[ix], &[&rich]).await),
+        Some(1024)
+    );
+
+    //exactly at the token cap still qualifies
+    let (user, user_token) = new_user(&mut env).await;
+    mint_tokens(&mut env, &user_token, 500_000).await;
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    send(&mut env, &[ix], &[&user]).await.unwrap();
+    assert_eq!(token_balance(&mut env, &user_token).await, 1_500_000);
+
+    //now above the cap
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    assert_eq!(
+        custom_error(send(&mut env, &[ix], &[&user]).await),
+        Some(1023)
+    );
+
+    //an empty looking copy of the whale's account outside the token program doesn't pass the cap
+    let mut fake = env
+        .context
+        .banks_client
+        .get_account(whale_token)
+        .await
+        .unwrap()
+        .unwrap();
+    let mut token_account = TokenAccount::unpack(&fake.data).unwrap();
+    token_account.amount = 0;
+    TokenAccount::pack(token_account, &mut fake.data).unwrap();
+    fake.owner = env.program_id;
+    let fake_token = Pubkey::new_unique();
+    env.context
+        .set_account(&fake_token, &AccountSharedData::from(fake));
+
+    let ix = claim_ix(&env, &whale.pubkey(), &fake_token, None);
+    assert!(is_invalid_account_data(
+        send(&mut env, &[ix], &[&whale]).await
+    ));
+}
+
+fn fee_config_update(claim_fee_lamports: u64, treasurer: Option<Pubkey>) -> FaucetInstruction {
+    FaucetInstruction::UpdateFaucetConfig(ConfigUpdate {
+        claim_fee_lamports: Some(claim_fee_lamports),
+        treasurer: Some(treasurer),
+        ..Default::default()
+    })
+}
+
+#[tokio::test]
+// Claim fee set → claimer pays the fee vault → only the treasurer can sweep it
+async fn test_claim_fee_and_withdraw() {
+    let mut env = setup_faucet(1_000_000, 0, 10_000_000).await;
+    let admin = env.admin.insecure_clone();
+    let treasurer = Keypair::new();
+    let (fee_vault, _) = Pubkey::find_program_address(&[b"fee_vault"], &env.program_id);
+
+    //below rent exemption the vault couldn't take it
+    let ix = update_config_ix(&env, fee_config_update(1_000, Some(treasurer.pubkey())));
+    assert_eq!(
+        custom_error(send(&mut env, &[ix], &[&admin]).await),
+        Some(1006)
+    );
+
+    let fee = 5_000_000;
+    let ix = update_config_ix(&env, fee_config_update(fee, Some(treasurer.pubkey())));
+    send(&mut env, &[ix], &[&admin]).await.unwrap();
+
+    //fee vault account missing
+    let (user, user_token) = new_user(&mut env).await;
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    assert!(send(&mut env, &[ix], &[&user]).await.is_err());
+
+    for _ in 0..2 {
+        let (user, user_token) = new_user(&mut env).await;
+        let mut ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+        ix.accounts.push(AccountMeta::new(fee_vault, false));
+        send(&mut env, &[ix], &[&user]).await.unwrap();
+    }
+
+    let banks_client = &mut env.context.banks_client;
+    assert_eq!(banks_client.get_balance(fee_vault).await.unwrap(), 2 * fee);
+
+    let destination = Pubkey::new_unique();
+    let withdraw_ix = |signer: Pubkey| Instruction {
+        program_id: env.program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(signer, true),
+            AccountMeta::new_readonly(env.faucet_config_pda, false),
+            AccountMeta::new(fee_vault, false),
+            AccountMeta::new(destination, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: borsh::to_vec(&FaucetInstruction::WithdrawFees).unwrap(),
+    };
+
+    let admin_withdraw = withdraw_ix(admin.pubkey());
+    let treasurer_withdraw = withdraw_ix(treasurer.pubkey());
+
+    assert_eq!(
+        custom_error(send(&mut env, &[admin_withdraw], &[&admin]).await),
+        Some(1025)
+    );
+
+    send(&mut env, &[treasurer_withdraw], &[&treasurer])
+        .await
+        .unwrap();
+
+    let banks_client = &mut env.context.banks_client;
+    assert_eq!(
+        banks_client.get_balance(destination).await.unwrap(),
+        2 * fee
+    );
+    assert_eq!(banks_client.get_balance(fee_vault).await.unwrap(), 0);
+}
+
+fn bond_ix(env: &FaucetTestEnv, user: &Pubkey, instruction: FaucetInstruction) -> Instruction {
+    let (user_claim_pda, _) =
+        Pubkey::find_program_address(&[b"user_claim", user.as_ref()], &env.program_id);
+    let (fee_vault, _) = Pubkey::find_program_address(&[b"fee_vault"], &env.program_id);
+
+    let accounts = match instruction {
+        FaucetInstruction::SlashBond { .. } => vec![
+            AccountMeta::new_readonly(env.admin.pubkey(), true),
+            AccountMeta::new_readonly(env.faucet_config_pda, false),
+            AccountMeta::new(user_claim_pda, false),
+            AccountMeta::new(fee_vault, false),
+        ],
+        _ => vec![
+            AccountMeta::new(*user, true),
+            AccountMeta::new(user_claim_pda, false),
+        ],
+    };
+
+    Instruction {
+        program_id: env.program_id,
+        accounts,
+        data: borsh::to_vec(&instruction).unwrap(),
+    }
+}
+
+#[tokio::test]
+// Claim locks a bond → reclaimed after the holding period → slashing bans the wallet
+async fn test_claim_bond() {
+    let mut env = setup_faucet(1_000_000, 0, 10_000_000).await;
+    set_clock(&mut env, 1_000).await;
+
+    let bond = 10_000_000;
+    let update_ix = update_config_ix(
+        &env,
+        FaucetInstruction::UpdateFaucetConfig(ConfigUpdate {
+            claim_bond_lamports: Some(bond),
+            bond_holding_seconds: Some(3_600),
+            ..Default::default()
+        }),
+    );
+    let admin = env.admin.insecure_clone();
+    send(&mut env, &[update_ix], &[&admin]).await.unwrap();
+
+    let (user, user_token) = new_user(&mut env).await;
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    send(&mut env, &[ix], &[&user]).await.unwrap();
+
+    let record = load_user_record(&mut env, &user.pubkey()).await;
+    assert_eq!(record.bonded_lamports, bond);
+    assert_eq!(record.bond_unlocks_at, 4_600);
+
+    let ix = bond_ix(&env, &user.pubkey(), FaucetInstruction::ReclaimBond);
+    assert_eq!(
+        custom_error(send(&mut env, &[ix], &[&user]).await),
+        Some(1027)
+    );
+
+    set_clock(&mut env, 4_600).await;
+    let before = env
+        .context
+        .banks_client
+        .get_balance(user.pubkey())
+        .await
+        .unwrap();
+    let ix = bond_ix(&env, &user.pubkey(), FaucetInstruction::ReclaimBond);
+    send(&mut env, &[ix], &[&user]).await.unwrap();
+    let after = env
+        .context
+        .banks_client
+        .get_balance(user.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(after - before, bond);
+    assert_eq!(
+        load_user_record(&mut env, &user.pubkey())
+            .await
+            .bonded_lamports,
+        0
+    );
+
+    //bond again, then get slashed
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    send(&mut env, &[ix], &[&user]).await.unwrap();
+
+    let slash = FaucetInstruction::SlashBond {
+        user: user.pubkey(),
+    };
+    let ix = bond_ix(&env, &user.pubkey(), slash);
+    send(&mut env, &[ix], &[&admin]).await.unwrap();
+
+    let record = load_user_record(&mut env, &user.pubkey()).await;
+    assert!(record.banned);
+    assert_eq!(record.bonded_lamports, 0);
+
+    let (fee_vault, _) = Pubkey::find_program_address(&[b"fee_vault"], &env.program_id);
+    let banks_client = &mut env.context.banks_client;
+    assert_eq!(banks_client.get_balance(fee_vault).await.unwrap(), bond);
+
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    assert_eq!(
+        custom_error(send(&mut env, &[ix], &[&user]).await),
+        Some(1026)
+    );
+
+    set_clock(&mut env, 10_000).await;
+    let ix = bond_ix(&env, &user.pubkey(), FaucetInstruction::ReclaimBond);
+    assert_eq!(
+        custom_error(send(&mut env, &[ix], &[&user]).await),
+        Some(1026)
+    );
+}
+
+#[tokio::test]
+// Claim with a bond → slashed and banned → a valid voucher can't get around the ban
+async fn test_banned_user_voucher_rejected() {
+    let mut env = setup_faucet(1_000_000, 0, 10_000_000).await;
+    set_clock(&mut env, 1_000).await;
+
+    let authority = Keypair::new();
+    let update_ix = update_config_ix(
+        &env,
+        FaucetInstruction::UpdateFaucetConfig(ConfigUpdate {
+            claim_bond_lamports: Some(10_000_000),
+            bond_holding_seconds: Some(3_600),
+            voucher_authority: Some(Some(authority.pubkey())),
+            ..Default::default()
+        }),
+    );
+    let admin = env.admin.insecure_clone();
+    send(&mut env, &[update_ix], &[&admin]).await.unwrap();
+
+    let (user, user_token) = new_user(&mut env).await;
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    send(&mut env, &[ix], &[&user]).await.unwrap();
+
+    let slash = FaucetInstruction::SlashBond {
+        user: user.pubkey(),
+    };
+    let ix = bond_ix(&env, &user.pubkey(), slash);
+    send(&mut env, &[ix], &[&admin]).await.unwrap();
+
+    let voucher = ClaimVoucher {
+        faucet: env.faucet_config_pda,
+        user: user.pubkey(),
+        amount: 3_000_000,
+        nonce: 1,
+        expiry: 2_000,
+    };
+    let ixs = voucher_ixs(&env, &authority, &user.pubkey(), &user_token, voucher);
+    assert_eq!(
+        custom_error(send(&mut env, &ixs, &[&user]).await),
+        Some(1026)
+    );
+    assert_eq!(token_balance(&mut env, &user_token).await, 1_000_000);
+}
+
+#[tokio::test]
+// Claim → return the tokens → allowance restored so the user can claim again right away
+async fn test_return_tokens_restores_allowance() {
+    let mut env = setup_faucet(1_000_000, 3_600, 10_000_000).await;
+
+    let (user, user_token) = new_user(&mut env).await;
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    send(&mut env, &[ix], &[&user]).await.unwrap();
+
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    assert_eq!(
+        custom_error(send(&mut env, &[ix], &[&user]).await),
+        Some(1000)
+    );
+
+    let (user_claim_pda, _) =
+        Pubkey::find_program_address(&[b"user_claim", user.pubkey().as_ref()], &env.program_id);
+    let return_ix = Instruction {
+        program_id: env.program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(user.pubkey(), true),
+            AccountMeta::new(user_claim_pda, false),
+            AccountMeta::new(user_token, false),
+            AccountMeta::new(env.treasury, false),
+            AccountMeta::new_readonly(env.faucet_config_pda, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: borsh::to_vec(&FaucetInstruction::ReturnTokens { amount: 600_000 }).unwrap(),
+    };
+
+    //returning to another account owned by the config PDA earns no credit
+    let faucet_config_pda = env.faucet_config_pda;
+    let decoy = create_token_account(&mut env, &faucet_config_pda).await;
+    let mut decoy_ix = return_ix.clone();
+    decoy_ix.accounts[3] = AccountMeta::new(decoy, false);
+    assert!(is_invalid_account_data(
+        send(&mut env, &[decoy_ix], &[&user]).await
+    ));
+    assert_eq!(token_balance(&mut env, &decoy).await, 0);
+
+    send(&mut env, &[return_ix], &[&user]).await.unwrap();
+
+    let treasury = env.treasury;
+    assert_eq!(token_balance(&mut env, &treasury).await, 9_600_000);
+    let record = load_user_record(&mut env, &user.pubkey()).await;
+    assert_eq!(record.total_returned, 600_000);
+    assert_eq!(record.allowance, 600_000);
+
+    //returned tokens can be claimed again, but not more
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, Some(700_000));
+    assert_eq!(
+        custom_error(send(&mut env, &[ix], &[&user]).await),
+        Some(1000)
+    );
+
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, Some(600_000));
+    send(&mut env, &[ix], &[&user]).await.unwrap();
+    assert_eq!(token_balance(&mut env, &user_token).await, 1_000_000);
+}
+
+#[tokio::test]
+// Daily claims grow the streak multiplier up to the cap → a missed day resets it
+// the default bucket only holds one base claim, so boosts must be paid on top of it
+async fn test_claim_streaks() {
+    let mut env = setup_faucet(1_000_000, 60, 100_000_000).await;
+    let day = 86_400;
+    set_clock(&mut env, 10 * day).await;
+
+    let update_ix = update_config_ix(
+        &env,
+        FaucetInstruction::UpdateFaucetConfig(ConfigUpdate {
+            streak: Some(Some(StreakPolicy {
+                period_seconds: day,
+                multipliers_bps: vec![10_000, 15_000, 20_000],
+                max_tokens_per_claim: 1_800_000,
+            })),
+            ..Default::default()
+        }),
+    );
+    let admin = env.admin.insecure_clone();
+    send(&mut env, &[update_ix], &[&admin]).await.unwrap();
+
+    let (user, user_token) = new_user(&mut env).await;
+
+    //(clock, streak, tokens received)
+    let claims = [
+        (10 * day, 1, 1_000_000),
+        (10 * day + 3_600, 1, 1_000_000), //same day keeps the streak
+        (11 * day, 2, 1_500_000),
+        (12 * day, 3, 1_800_000), //2x capped
+        (13 * day, 4, 1_800_000),
+        (15 * day, 1, 1_000_000), //missed a day
+    ];
+
+    for (now, streak, tokens) in claims {
+        set_clock(&mut env, now).await;
+        let before = token_balance(&mut env, &user_token).await;
+        let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+        send(&mut env, &[ix], &[&user]).await.unwrap();
+
+        assert_eq!(token_balance(&mut env, &user_token).await - before, tokens);
+        assert_eq!(
+            load_user_record(&mut env, &user.pubkey()).await.streak,
+            streak
+        );
+    }
+}
+
+//claim with the referrer's record and referral PDA appended
+fn referred_claim_ix(
+    env: &FaucetTestEnv,
+    user: &Pubkey,
+    user_token: &Pubkey,
+    referrer: &Pubkey,
+) -> Instruction {
+    let (referrer_claim_pda, _) =
+        Pubkey::find_program_address(&[b"user_claim", referrer.as_ref()], &env.program_id);
+    let (referral_pda, _) =
+        Pubkey::find_program_address(&[b"referral", referrer.as_ref()], &env.program_id);
+
+    let mut ix = claim_ix(env, user, user_token, None);
+    ix.data = borsh::to_vec(&FaucetInstruction::ClaimTokens {
+        amount: None,
+        referrer: Some(*referrer),
+    })
+    .unwrap();
+    ix.accounts
+        .push(AccountMeta::new_readonly(referrer_claim_pda, false));
+    ix.accounts.push(AccountMeta::new(referral_pda, false));
+    ix
+}
+
+#[tokio::test]
+// Referred first claims credit the referrer once → self referrals fail → bonus is withdrawable
+async fn test_referral_bonus() {
+    let mut env = setup_faucet(1_000_000, 0, 100_000_000).await;
+
+    let update_ix = update_config_ix(
+        &env,
+        FaucetInstruction::UpdateFaucetConfig(ConfigUpdate {
+            referral_bonus: Some(250_000),
+            ..Default::default()
+        }),
+    );
+    let admin = env.admin.insecure_clone();
+    send(&mut env, &[update_ix], &[&admin]).await.unwrap();
+
+    let (referrer, referrer_token) = new_user(&mut env).await;
+
+    //referrer hasn't claimed yet, so there is no record to refer from
+    let (early, early_token) = new_user(&mut env).await;
+    let ix = referred_claim_ix(&env, &early.pubkey(), &early_token, &referrer.pubkey());
+    send(&mut env, &[ix], &[&early]).await.unwrap();
+    assert_eq!(
+        load_user_record(&mut env, &early.pubkey()).await.referrer,
+        None
+    );
+
+    let ix = claim_ix(&env, &referrer.pubkey(), &referrer_token, None);
+    send(&mut env, &[ix], &[&referrer]).await.unwrap();
+
+    let (referral_pda, _) =
+        Pubkey::find_program_address(&[b"referral", referrer.pubkey().as_ref()], &env.program_id);
+
+    for _ in 0..2 {
+        let (user, user_token) = new_user(&mut env).await;
+        let ix = referred_claim_ix(&env, &user.pubkey(), &user_token, &referrer.pubkey());
+        send(&mut env, &[ix], &[&user]).await.unwrap();
+        assert_eq!(
+            load_user_record(&mut env, &user.pubkey()).await.referrer,
+            Some(referrer.pubkey())
+        );
+
+        //later claims don't pay again
+        let ix = referred_claim_ix(&env, &user.pubkey(), &user_token, &referrer.pubkey());
+        send(&mut env, &[ix], &[&user]).await.unwrap();
+    }
+
+    let account = env
+        .context
+        .banks_client
+        .get_account(referral_pda)
+        .await
+        .unwrap()
+        .unwrap();
+    let referral = ReferralAccount::try_from_slice(&account.data).unwrap();
+    assert_eq!(referral.claimable, 500_000);
+    assert_eq!(referral.referrals, 2);
+
+    //referring yourself
+    let (cheater, cheater_token) = new_user(&mut env).await;
+    let ix = referred_claim_ix(&env, &cheater.pubkey(), &cheater_token, &cheater.pubkey());
+    assert_eq!(
+        custom_error(send(&mut env, &[ix], &[&cheater]).await),
+        Some(1028)
+    );
+
+    let withdraw_ix = Instruction {
+        program_id: env.program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(referrer.pubkey(), true),
+            AccountMeta::new(referral_pda, false),
+            AccountMeta::new(referrer_token, false),
+            AccountMeta::new(env.treasury, false),
+            AccountMeta::new_readonly(env.faucet_config_pda, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: borsh::to_vec(&FaucetInstruction::WithdrawReferralBonus).unwrap(),
+    };
+    send(&mut env, std::slice::from_ref(&withdraw_ix), &[&referrer])
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut env, &referrer_token).await, 1_500_000);
+
+    assert_eq!(
+        custom_error(send(&mut env, &[withdraw_ix], &[&referrer]).await),
+        Some(1002)
+    );
+}
+
+#[tokio::test]
+// Vesting claim lands in escrow → nothing before the cliff → linear release → fully vested
+async fn test_vesting_claims() {
+    let mut env = setup_faucet(1_000_000, 0, 10_000_000).await;
+    set_clock(&mut env, 1_000).await;
+
+    let update_ix = update_config_ix(
+        &env,
+        FaucetInstruction::UpdateFaucetConfig(ConfigUpdate {
+            vesting: Some(Some(VestingSchedule {
+                cliff_seconds: 100,
+                duration_seconds: 1_000,
+            })),
+            ..Default::default()
+        }),
+    );
+    let admin = env.admin.insecure_clone();
+    send(&mut env, &[update_ix], &[&admin]).await.unwrap();
+
+    let (user, user_token) = new_user(&mut env).await;
+    let (vesting_pda, _) =
+        Pubkey::find_program_address(&[b"vesting", user.pubkey().as_ref()], &env.program_id);
+    let (escrow_pda, _) = Pubkey::find_program_address(
+        &[b"vesting_escrow", user.pubkey().as_ref()],
+        &env.program_id,
+    );
+
+    let mut ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    ix.accounts.push(AccountMeta::new(vesting_pda, false));
+    ix.accounts.push(AccountMeta::new(escrow_pda, false));
+    ix.accounts
+        .push(AccountMeta::new_readonly(env.mint.pubkey(), false));
+    send(&mut env, &[ix], &[&user]).await.unwrap();
+
+    assert_eq!(token_balance(&mut env, &user_token).await, 0);
+    assert_eq!(token_balance(&mut env, &escrow_pda).await, 1_000_000);
+
+    //the escrow is spendable by the user's vesting PDA only
+    let escrow = env
+        .context
+        .banks_client
+        .get_account(escrow_pda)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        TokenAccount::unpack(&escrow.data).unwrap().owner,
+        vesting_pda
+    );
+
+    let release_ix = Instruction {
+        program_id: env.program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(user.pubkey(), true),
+            AccountMeta::new(vesting_pda, false),
+            AccountMeta::new(escrow_pda, false),
+            AccountMeta::new(user_token, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: borsh::to_vec(&FaucetInstruction::Release).unwrap(),
+    };
+
+    //before the cliff
+    set_clock(&mut env, 1_050).await;
+    let res = send(&mut env, std::slice::from_ref(&release_ix), &[&user]).await;
+    assert_eq!(custom_error(res), Some(1029));
+
+    //halfway
+    set_clock(&mut env, 1_500).await;
+    send(&mut env, std::slice::from_ref(&release_ix), &[&user])
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut env, &user_token).await, 500_000);
+
+    set_clock(&mut env, 5_000).await;
+    send(&mut env, std::slice::from_ref(&release_ix), &[&user])
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut env, &user_token).await, 1_000_000);
+    assert_eq!(token_balance(&mut env, &escrow_pda).await, 0);
+
+    let res = send(&mut env, &[release_ix], &[&user]).await;
+    assert_eq!(custom_error(res), Some(1029));
+}
+
+//appends the vesting accounts a claim needs while vesting is on
+fn push_vesting_accounts(env: &FaucetTestEnv, ix: &mut Instruction, user: &Pubkey) {
+    let (vesting_pda, _) =
+        Pubkey::find_program_address(&[b"vesting", user.as_ref()], &env.program_id);
+    let (escrow_pda, _) =
+        Pubkey::find_program_address(&[b"vesting_escrow", user.as_ref()], &env.program_id);
+
+    ix.accounts.push(AccountMeta::new(vesting_pda, false));
+    ix.accounts.push(AccountMeta::new(escrow_pda, false));
+    ix.accounts
+        .push(AccountMeta::new_readonly(env.mint.pubkey(), false));
+}
+
+#[tokio::test]
+// Claim → second claim halfway through → the first grant still vests on its own schedule
+async fn test_vesting_grants_keep_their_schedule() {
+    let mut env = setup_faucet(1_000_000, 0, 10_000_000).await;
+    set_clock(&mut env, 1_000).await;
+
+    let update_ix = update_config_ix(
+        &env,
+        FaucetInstruction::UpdateFaucetConfig(ConfigUpdate {
+            vesting: Some(Some(VestingSchedule {
+                cliff_seconds: 100,
+                duration_seconds: 1_000,
+            })),
+            ..Default::default()
+        }),
+    );
+    let admin = env.admin.insecure_clone();
+    send(&mut env, &[update_ix], &[&admin]).await.unwrap();
+
+    let (user, user_token) = new_user(&mut env).await;
+    let mut ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    push_vesting_accounts(&env, &mut ix, &user.pubkey());
+    send(&mut env, &[ix], &[&user]).await.unwrap();
+
+    //the second claim pays out the vested half of the first one
+    set_clock(&mut env, 1_500).await;
+    let mut ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    push_vesting_accounts(&env, &mut ix, &user.pubkey());
+    send(&mut env, &[ix], &[&user]).await.unwrap();
+    assert_eq!(token_balance(&mut env, &user_token).await, 500_000);
+
+    let (vesting_pda, _) =
+        Pubkey::find_program_address(&[b"vesting", user.pubkey().as_ref()], &env.program_id);
+    let (escrow_pda, _) = Pubkey::find_program_address(
+        &[b"vesting_escrow", user.pubkey().as_ref()],
+        &env.program_id,
+    );
+    let release_ix = Instruction {
+        program_id: env.program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(user.pubkey(), true),
+            AccountMeta::new(vesting_pda, false),
+            AccountMeta::new(escrow_pda, false),
+            AccountMeta::new(user_token, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: borsh::to_vec(&FaucetInstruction::Release).unwrap(),
+    };
+
+    //first grant done, second one halfway
+    set_clock(&mut env, 2_000).await;
+    send(&mut env, std::slice::from_ref(&release_ix), &[&user])
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut env, &user_token).await, 1_500_000);
+
+    set_clock(&mut env, 2_500).await;
+    send(&mut env, &[release_ix], &[&user]).await.unwrap();
+    assert_eq!(token_balance(&mut env, &user_token).await, 2_000_000);
+    assert_eq!(token_balance(&mut env, &escrow_pda).await, 0);
+}
+
+#[tokio::test]
+// Vesting PDA passed as the config → the user can't rewrite their own grants through UpdateFaucetConfig
+async fn test_update_rejects_non_config_accounts() {
+    let mut env = setup_faucet(1_000_000, 0, 10_000_000).await;
+    set_clock(&mut env, 1_000).await;
+
+    let update_ix = update_config_ix(
+        &env,
+        FaucetInstruction::UpdateFaucetConfig(ConfigUpdate {
+            vesting: Some(Some(VestingSchedule {
+                cliff_seconds: 100,
+                duration_seconds: 1_000,
+            })),
+            ..Default::default()
+        }),
+    );
+    let admin = env.admin.insecure_clone();
+    send(&mut env, &[update_ix], &[&admin]).await.unwrap();
+
+    //two grants start with the user's key, just like a config starts with its admin
+    let (user, user_token) = new_user(&mut env).await;
+    for time in [1_000, 1_010] {
+        set_clock(&mut env, time).await;
+        let mut ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+        push_vesting_accounts(&env, &mut ix, &user.pubkey());
+        send(&mut env, &[ix], &[&user]).await.unwrap();
+    }
+
+    let (vesting_pda, _) =
+        Pubkey::find_program_address(&[b"vesting", user.pubkey().as_ref()], &env.program_id);
+    let before = env
+        .context
+        .banks_client
+        .get_account(vesting_pda)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let mut ix = update_config_ix(
+        &env,
+        FaucetInstruction::UpdateFaucetConfig(ConfigUpdate {
+            tokens_per_claim: Some(0),
+            cooldown_seconds: Some(1),
+            ..Default::default()
+        }),
+    );
+    ix.accounts[0] = AccountMeta::new(user.pubkey(), true);
+    ix.accounts[1] = AccountMeta::new(vesting_pda, false);
+    assert!(is_invalid_account_data(
+        send(&mut env, &[ix], &[&user]).await
+    ));
+
+    let after = env
+        .context
+        .banks_client
+        .get_account(vesting_pda)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(before.data, after.data);
+}
+
+#[tokio::test]
+// Vesting on → referred first claim → second claim's vesting PDA isn't mistaken for a referrer
+async fn test_repeat_vesting_claims_with_referral() {
+    let mut env = setup_faucet(1_000_000, 0, 10_000_000).await;
+    set_clock(&mut env, 1_000).await;
+
+    let update_ix = update_config_ix(
+        &env,
+        FaucetInstruction::UpdateFaucetConfig(ConfigUpdate {
+            referral_bonus: Some(250_000),
+            vesting: Some(Some(VestingSchedule {
+                cliff_seconds: 100,
+                duration_seconds: 1_000,
+            })),
+            ..Default::default()
+        }),
+    );
+    let admin = env.admin.insecure_clone();
+    send(&mut env, &[update_ix], &[&admin]).await.unwrap();
+
+    let (referrer, referrer_token) = new_user(&mut env).await;
+    let mut ix = claim_ix(&env, &referrer.pubkey(), &referrer_token, None);
+    push_vesting_accounts(&env, &mut ix, &referrer.pubkey());
+    send(&mut env, &[ix], &[&referrer]).await.unwrap();
+
+    let (user, user_token) = new_user(&mut env).await;
+    let mut ix = referred_claim_ix(&env, &user.pubkey(), &user_token, &referrer.pubkey());
+    push_vesting_accounts(&env, &mut ix, &user.pubkey());
+    send(&mut env, &[ix], &[&user]).await.unwrap();
+
+    set_clock(&mut env, 1_010).await;
+    let mut ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    push_vesting_accounts(&env, &mut ix, &user.pubkey());
+    send(&mut env, &[ix], &[&user]).await.unwrap();
+
+    let (escrow_pda, _) = Pubkey::find_program_address(
+        &[b"vesting_escrow", user.pubkey().as_ref()],
+        &env.program_id,
+    );
+    assert_eq!(token_balance(&mut env, &escrow_pda).await, 2_000_000);
+    assert_eq!(
+        load_user_record(&mut env, &user.pubkey()).await.referrer,
+        Some(referrer.pubkey())
+    );
+
+    let (referral_pda, _) =
+        Pubkey::find_program_address(&[b"referral", referrer.pubkey().as_ref()], &env.program_id);
+    let account = env
+        .context
+        .banks_client
+        .get_account(referral_pda)
+        .await
+        .unwrap()
+        .unwrap();
+    let referral = ReferralAccount::try_from_slice(&account.data).unwrap();
+    assert_eq!(referral.referrals, 1);
+    assert_eq!(referral.claimable, 250_000);
+}
+
+#[tokio::test]
+// Basket of two extra mints → one claim pays all three → wrong or missing accounts fail
+async fn test_basket_claim() {
+    let mut env = setup_faucet(1_000_000, 3_600, 10_000_000).await;
+    let faucet_config_pda = env.faucet_config_pda;
+
+    let mut basket = vec![];
+    for amount in [5_000_000, 1] {
+        let mint = create_mint(&mut env).await;
+        let treasury = create_token_account_for_mint(&mut env, &mint, &faucet_config_pda).await;
+        mint_tokens_of(&mut env, &mint, &treasury, 10 * amount).await;
+        basket.push(BasketEntry {
+            mint,
+            treasury,
+            amount,
+        });
+    }
+
+    let update_ix = update_config_ix(
+        &env,
+        FaucetInstruction::UpdateFaucetConfig(ConfigUpdate {
+            basket: Some(basket.clone()),
+            ..Default::default()
+        }),
+    );
+    let admin = env.admin.insecure_clone();
+    send(&mut env, &[update_ix], &[&admin]).await.unwrap();
+
+    let (user, user_token) = new_user(&mut env).await;
+    let mut basket_tokens = vec![];
+    for entry in &basket {
+        basket_tokens
+            .push(create_token_account_for_mint(&mut env, &entry.mint, &user.pubkey()).await);
+    }
+
+    //basket accounts missing
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    assert!(send(&mut env, &[ix], &[&user]).await.is_err());
+
+    //entries out of order
+    let mut ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    for (entry, token) in basket.iter().zip(&basket_tokens).rev() {
+        ix.accounts.push(AccountMeta::new(entry.treasury, false));
+        ix.accounts.push(AccountMeta::new(*token, false));
+    }
+    assert!(send(&mut env, &[ix], &[&user]).await.is_err());
+
+    let mut ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    for (entry, token) in basket.iter().zip(&basket_tokens) {
+        ix.accounts.push(AccountMeta::new(entry.treasury, false));
+        ix.accounts.push(AccountMeta::new(*token, false));
+    }
+    send(&mut env, std::slice::from_ref(&ix), &[&user])
+        .await
+        .unwrap();
+
+    assert_eq!(token_balance(&mut env, &user_token).await, 1_000_000);
+    assert_eq!(token_balance(&mut env, &basket_tokens[0]).await, 5_000_000);
+    assert_eq!(token_balance(&mut env, &basket_tokens[1]).await, 1);
+
+    //one shared cooldown for the whole basket
+    assert_eq!(
+        custom_error(send(&mut env, &[ix], &[&user]).await),
+        Some(1000)
+    );
+    assert_eq!(token_balance(&mut env, &basket_tokens[0]).await, 5_000_000);
+}
+
+#[tokio::test]
+// Lottery needs anti bot and tiers within one claim → turning either off later is rejected too
+async fn test_lottery_config_checks() {
+    let mut env = setup_faucet(1_000_000, 0, 1_000_000_000).await;
+    let admin = env.admin.insecure_clone();
+    let lottery = Lottery {
+        tiers: vec![
+            LotteryTier {
+                weight: 90,
+                amount: 100,
+            },
+            LotteryTier {
+                weight: 10,
+                amount: 10_000,
+            },
+        ],
+    };
+    let bucket = |burst| RateLimitPolicy::TokenBucket {
+        refill_amount: 100,
+        refill_interval: 60,
+        burst,
+    };
+
+    let update = |env: &FaucetTestEnv, update: ConfigUpdate| {
+        update_config_ix(env, FaucetInstruction::UpdateFaucetConfig(update))
+    };
+
+    //no anti bot
+    let ix = update(
+        &env,
+        ConfigUpdate {
+            lottery: Some(Some(lottery.clone())),
+            ..Default::default()
+        },
+    );
+    assert_eq!(
+        custom_error(send(&mut env, &[ix], &[&admin]).await),
+        Some(1006)
+    );
+
+    //jackpot above the burst
+    let ix = update(
+        &env,
+        ConfigUpdate {
+            rate_limit: Some(bucket(5_000)),
+            anti_bot: Some(true),
+            lottery: Some(Some(lottery.clone())),
+            ..Default::default()
+        },
+    );
+    assert_eq!(
+        custom_error(send(&mut env, &[ix], &[&admin]).await),
+        Some(1006)
+    );
+
+    let ix = update(
+        &env,
+        ConfigUpdate {
+            rate_limit: Some(bucket(10_000)),
+            anti_bot: Some(true),
+            lottery: Some(Some(lottery.clone())),
+            ..Default::default()
+        },
+    );
+    send(&mut env, &[ix], &[&admin]).await.unwrap();
+
+    for undo in [
+        ConfigUpdate {
+            anti_bot: Some(false),
+            ..Default::default()
+        },
+        ConfigUpdate {
+            rate_limit: Some(bucket(9_999)),
+            ..Default::default()
+        },
+    ] {
+        let ix = update(&env, undo);
+        assert_eq!(
+            custom_error(send(&mut env, &[ix], &[&admin]).await),
+            Some(1006)
+        );
+    }
+
+    let config = load_config(&mut env).await;
+    assert!(config.anti_bot);
+    assert_eq!(config.lottery, Some(lottery));
+}
+
+#[tokio::test]
+// Lottery faucet → every claim pays one of the tier amounts → slot hashes sysvar is required
+async fn test_lottery_claims() {
+    let mut env = setup_faucet(1_000_000, 0, 1_000_000_000).await;
+
+    let update_ix = update_config_ix(
+        &env,
+        FaucetInstruction::UpdateFaucetConfig(ConfigUpdate {
+            rate_limit: Some(RateLimitPolicy::FixedCooldown),
+            anti_bot: Some(true),
+            lottery: Some(Some(Lottery {
+                tiers: vec![
+                    LotteryTier {
+                        weight: 90,
+                        amount: 100,
+                    },
+                    LotteryTier {
+                        weight: 10,
+                        amount: 10_000,
+                    },
+                ],
+            })),
+            ..Default::default()
+        }),
+    );
+    let admin = env.admin.insecure_clone();
+    send(&mut env, &[update_ix], &[&admin]).await.unwrap();
+
+    let (user, user_token) = new_user(&mut env).await;
+
+    let mut ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    ix.accounts.push(AccountMeta::new_readonly(
+        solana_program::sysvar::instructions::id(),
+        false,
+    ));
+    assert!(send(&mut env, &[ix], &[&user]).await.is_err());
+
+    for claims in 1..=5 {
+        let before = token_balance(&mut env, &user_token).await;
+        let ix = lottery_claim_ix(&env, &user.pubkey(), &user_token, None);
+        send(&mut env, &[ix], &[&user]).await.unwrap();
+
+        let drawn = token_balance(&mut env, &user_token).await - before;
+        assert!(drawn == 100 || drawn == 10_000, "drew {drawn}");
+        assert_eq!(
+            load_user_record(&mut env, &user.pubkey())
+                .await
+                .total_claims,
+            claims
+        );
+    }
+}
+
+//claim with the sysvars anti bot checks and lottery draws read
+fn lottery_claim_ix(
+    env: &FaucetTestEnv,
+    user: &Pubkey,
+    user_token: &Pubkey,
+    amount: Option<u64>,
+) -> Instruction {
+    let mut ix = claim_ix(env, user, user_token, amount);
+    ix.accounts.push(AccountMeta::new_readonly(
+        solana_program::sysvar::instructions::id(),
+        false,
+    ));
+    ix.accounts.push(AccountMeta::new_readonly(
+        solana_program::sysvar::slot_hashes::id(),
+        false,
+    ));
+    ix
+}
+
+#[tokio::test]
+// Lottery on the default bucket → any draw uses up the claim, so losing draws can't be rerolled
+// → prizes halve with the emission curve instead of being clipped
+async fn test_lottery_draws_cost_a_full_claim() {
+    let mut env = setup_faucet(1_000_000, 60, 1_000_000_000).await;
+
+    let update_ix = update_config_ix(
+        &env,
+        FaucetInstruction::UpdateFaucetConfig(ConfigUpdate {
+            emission: Some(EmissionCurve::StepHalving {
+                interval_seconds: 1_000,
+            }),
+            anti_bot: Some(true),
+            lottery: Some(Some(Lottery {
+                tiers: vec![
+                    LotteryTier {
+                        weight: 90,
+                        amount: 100,
+                    },
+                    LotteryTier {
+                        weight: 10,
+                        amount: 1_000_000,
+                    },
+                ],
+            })),
+            ..Default::default()
+        }),
+    );
+    let admin = env.admin.insecure_clone();
+    send(&mut env, &[update_ix], &[&admin]).await.unwrap();
+
+    let created_at = load_config(&mut env).await.created_at;
+    let (user, user_token) = new_user(&mut env).await;
+
+    //the draw decides the amount
+    let ix = lottery_claim_ix(&env, &user.pubkey(), &user_token, Some(100));
+    let res = send(&mut env, &[ix], &[&user]).await;
+    assert_eq!(custom_error(res), Some(1004));
+
+    for (offset, prizes) in [(100, [100, 1_000_000]), (1_500, [50, 500_000])] {
+        set_clock(&mut env, created_at + offset).await;
+        let before = token_balance(&mut env, &user_token).await;
+        let ix = lottery_claim_ix(&env, &user.pubkey(), &user_token, None);
+        send(&mut env, &[ix], &[&user]).await.unwrap();
+
+        let drawn = token_balance(&mut env, &user_token).await - before;
+        assert!(prizes.contains(&drawn), "drew {drawn}");
+
+        let ix = lottery_claim_ix(&env, &user.pubkey(), &user_token, None);
+        let res = send(&mut env, &[ix], &[&user]).await;
+        assert_eq!(custom_error(res), Some(1000));
+    }
+}
+
+//writes borsh state straight into a program owned account
+async fn set_program_account<T: borsh::BorshSerialize>(
+    env: &mut FaucetTestEnv,
+    address: &Pubkey,
+    state: &T,
+) {
+    let data = borsh::to_vec(state).unwrap();
+    let rent = env.context.banks_client.get_rent().await.unwrap();
+    let account = Account {
+        lamports: rent.minimum_balance(data.len()),
+        data,
+        owner: env.program_id,
+        executable: false,
+        rent_epoch: 0,
+    };
+    env.context
+        .set_account(address, &AccountSharedData::from(account));
+}
+
+fn migrate_ix(env: &FaucetTestEnv, admin: &Pubkey, records: &[Pubkey]) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*admin, true),
+        AccountMeta::new(env.faucet_config_pda, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(env.treasury, false),
+    ];
+    accounts.extend(
+        records
+            .iter()
+            .map(|record| AccountMeta::new(*record, false)),
+    );
+
+    Instruction {
+        program_id: env.program_id,
+        accounts,
+        data: borsh::to_vec(&FaucetInstruction::Migrate).unwrap(),
+    }
+}
+
+#[tokio::test]
+// First release config and record → claims fail → Migrate → old cooldown still applies
+async fn test_migrate_first_release_accounts() {
+    let mut env = setup_faucet(1_000_000, 60, 10_000_000).await;
+    let admin = env.admin.insecure_clone();
+    let now: Clock = env.context.banks_client.get_sysvar().await.unwrap();
+
+    let faucet_config_pda = env.faucet_config_pda;
+    let legacy_config = LegacyFaucetConfig {
+        admin: admin.pubkey(),
+        token_mint: env.mint.pubkey(),
+        tokens_per_claim: 1_000_000,
+        cooldown_seconds: 60,
+        is_active: false,
+    };
+    set_program_account(&mut env, &faucet_config_pda, &legacy_config).await;
+
+    let (user, user_token) = new_user(&mut env).await;
+    let (user_claim_pda, _) =
+        Pubkey::find_program_address(&[b"user_claim", user.pubkey().as_ref()], &env.program_id);
+    let legacy_record = LegacyClaimRecord {
+        user: user.pubkey(),
+        last_claim_time: now.unix_timestamp - 10,
+        total_claims: 3,
+    };
+    set_program_account(&mut env, &user_claim_pda, &legacy_record).await;
+
+    //the current program can't read either account yet
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    assert!(send(&mut env, &[ix], &[&user]).await.is_err());
+
+    let ix = migrate_ix(&env, &user.pubkey(), &[user_claim_pda]);
+    let res = send(&mut env, &[ix], &[&user]).await;
+    assert_eq!(custom_error(res), Some(1003));
+
+    let ix = migrate_ix(&env, &admin.pubkey(), &[user_claim_pda]);
+    send(&mut env, &[ix], &[&admin]).await.unwrap();
+
+    //the inactive faucet comes back paused with its old settings
+    let config = load_config(&mut env).await;
+    assert_eq!(config.admin, admin.pubkey());
+    assert_eq!(config.tokens_per_claim, 1_000_000);
+    assert_eq!(config.cooldown_seconds, 60);
+    assert_eq!(config.paused, PAUSE_CLAIMS);
+    assert_eq!(config.treasury, env.treasury);
+
+    let record = load_user_record(&mut env, &user.pubkey()).await;
+    assert_eq!(record.total_claims, 3);
+    assert_eq!(record.last_claim_time, now.unix_timestamp - 10);
+
+    //running it again leaves migrated accounts alone
+    let ix = migrate_ix(&env, &admin.pubkey(), &[user_claim_pda]);
+    send(&mut env, &[ix], &[&admin]).await.unwrap();
+
+    let resume_ix = pause_ix(
+        &env,
+        FaucetInstruction::Resume {
+            flags: PAUSE_CLAIMS,
+            reason: 1,
+            memo: None,
+        },
+    );
+    send(&mut env, &[resume_ix], &[&admin]).await.unwrap();
+
+    //the claim from before the migration still counts against the cooldown
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    let res = send(&mut env, &[ix], &[&user]).await;
+    assert_eq!(custom_error(res), Some(1000));
+
+    set_clock(&mut env, now.unix_timestamp + 60).await;
+    let ix = claim_ix(&env, &user.pubkey(), &user_token, None);
+    send(&mut env, &[ix], &[&user]).await.unwrap();
+
+    assert_eq!(token_balance(&mut env, &user_token).await, 1_000_000);
+    let record = load_user_record(&mut env, &user.pubkey()).await;
+    assert_eq!(record.total_claims, 4);
+}
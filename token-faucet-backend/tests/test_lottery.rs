@@ -0,0 +1,84 @@
+use solana_program::pubkey::Pubkey;
+use token_faucet_backend::{Lottery, LotteryTier};
+
+fn jackpot_lottery() -> Lottery {
+    Lottery {
+        tiers: vec![
+            LotteryTier {
+                weight: 90,
+                amount: 100,
+            },
+            LotteryTier {
+                weight: 10,
+                amount: 10_000,
+            },
+        ],
+    }
+}
+
+#[test]
+fn draws_only_configured_amounts_at_configured_odds() {
+    let lottery = jackpot_lottery();
+    let user = Pubkey::new_unique();
+    let draws = 20_000;
+
+    let mut jackpots = 0;
+    for total_claims in 0..draws {
+        let seed = Lottery::seed(&[7; 32], &user, total_claims);
+        let (tier, amount) = lottery.draw(seed);
+        assert_eq!(amount, lottery.tiers[tier as usize].amount);
+        if tier == 1 {
+            jackpots += 1;
+        }
+    }
+
+    //10% jackpot, allow a generous margin for a fixed sample
+    let jackpot_rate = jackpots as f64 / draws as f64;
+    assert!(
+        (0.08..0.12).contains(&jackpot_rate),
+        "jackpot rate {jackpot_rate}"
+    );
+}
+
+#[test]
+fn seed_depends_on_every_input() {
+    let user = Pubkey::new_unique();
+    let seed = Lottery::seed(&[1; 32], &user, 0);
+    assert_ne!(seed, Lottery::seed(&[2; 32], &user, 0));
+    assert_ne!(seed, Lottery::seed(&[1; 32], &Pubkey::new_unique(), 0));
+    assert_ne!(seed, Lottery::seed(&[1; 32], &user, 1));
+    assert_eq!(seed, Lottery::seed(&[1; 32], &user, 0));
+}
+
+#[test]
+fn draw_covers_the_whole_seed_range() {
+    let lottery = jackpot_lottery();
+    assert_eq!(lottery.draw(0), (0, 100));
+    assert_eq!(lottery.draw(89), (0, 100));
+    assert_eq!(lottery.draw(90), (1, 10_000));
+    assert_eq!(lottery.draw(99), (1, 10_000));
+    //u64::MAX % 100 == 15
+    assert_eq!(lottery.draw(u64::MAX), (0, 100));
+}
+
+#[test]
+fn invalid_lotteries_are_rejected() {
+    assert!(jackpot_lottery().validate().is_ok());
+    assert!(Lottery { tiers: vec![] }.validate().is_err());
+
+    let zero_weight = Lottery {
+        tiers: vec![LotteryTier {
+            weight: 0,
+            amount: 100,
+        }],
+    };
+    assert!(zero_weight.validate().is_err());
+
+    let zero_amount = Lottery {
+        tiers: vec![LotteryTier {
+            weight: 1,
+            amount: 0,
+        }],
+    };
+    assert!(zero_amount.validate().is_err());
+}
@@ -0,0 +1,269 @@
+use solana_program::pubkey::Pubkey;
+use token_faucet_backend::{MAX_WINDOW_CLAIMS, RateLimitPolicy, UserClaimedRecord};
+
+//small deterministic generator so every run replays the same clock sequences
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn range(&mut self, low: u64, high: u64) -> u64 {
+        low + self.next() % (high - low + 1)
+    }
+}
+
+//simulated claim attempts: (time, amount) with the clock only moving forward
+fn clock_sequence(
+    rng: &mut XorShift,
+    len: usize,
+    max_step: u64,
+    max_amount: u64,
+) -> Vec<(i64, u64)> {
+    let mut now = rng.range(0, 1_000_000) as i64;
+    (0..len)
+        .map(|_| {
+            now += rng.range(0, max_step) as i64;
+            (now, rng.range(1, max_amount))
+        })
+        .collect()
+}
+
+//runs a sequence through the policy, returning the accepted claims
+fn simulate(
+    policy: &RateLimitPolicy,
+    cooldown_seconds: i64,
+    tokens_per_claim: u64,
+    sequence: &[(i64, u64)],
+) -> Vec<(i64, u64)> {
+    let mut record = UserClaimedRecord::new(Pubkey::new_unique());
+    let mut accepted = vec![];
+
+    for &(now, amount) in sequence {
+        let before = borsh::to_vec(&record).unwrap();
+        match policy.try_consume(cooldown_seconds, tokens_per_claim, &mut record, now, amount) {
+            Ok(()) => {
+                record.total_claims += 1;
+                accepted.push((now, amount));
+            }
+            Err(wait) => {
+                assert!(wait > 0, "rejections must ask the user to wait");
+                assert_eq!(
+                    before,
+                    borsh::to_vec(&record).unwrap(),
+                    "rejection mutated state"
+                );
+            }
+        }
+    }
+
+    accepted
+}
+
+#[test]
+fn fixed_cooldown_spaces_claims() {
+    let mut rng = XorShift(0x9e37_79b9_7f4a_7c15);
+
+    for _ in 0..200 {
+        let cooldown = rng.range(1, 120) as i64;
+        let sequence = clock_sequence(&mut rng, 50, 60, 1_000);
+        let accepted = simulate(&RateLimitPolicy::FixedCooldown, cooldown, 1_000, &sequence);
+
+        assert!(!accepted.is_empty(), "first claim is always allowed");
+        for pair in accepted.windows(2) {
+            assert!(pair[1].0 - pair[0].0 >= cooldown);
+        }
+    }
+}
+
+#[test]
+fn token_bucket_never_exceeds_burst_plus_refill() {
+    let mut rng = XorShift(0xdead_beef_cafe_f00d);
+
+    for _ in 0..200 {
+        let refill_amount = rng.range(1, 1_000);
+        let refill_interval = rng.range(1, 60) as i64;
+        let burst = rng.range(1, 5_000);
+        let policy = RateLimitPolicy::TokenBucket {
+            refill_amount,
+            refill_interval,
+            burst,
+        };
+
+        let sequence = clock_sequence(&mut rng, 80, 30, burst);
+        let accepted = simulate(&policy, 0, 1_000, &sequence);
+
+        //from the first claim onwards, total spend is bounded by the bucket size plus refills
+        let Some(&(start, _)) = accepted.first() else {
+            continue;
+        };
+        let mut spent = 0u128;
+        for &(now, amount) in &accepted {
+            spent += amount as u128;
+            let refilled = (now - start) as u128 * refill_amount as u128 / refill_interval as u128;
+            assert!(spent <= burst as u128 + refilled);
+        }
+    }
+}
+
+#[test]
+fn config_bucket_follows_the_faucet_settings() {
+    let mut rng = XorShift(0x0f0f_1e1e_2d2d_3c3c);
+
+    for _ in 0..200 {
+        let tokens_per_claim = rng.range(1, 1_000);
+        let cooldown = rng.range(1, 60) as i64;
+        let bucket = RateLimitPolicy::TokenBucket {
+            refill_amount: tokens_per_claim,
+            refill_interval: cooldown,
+            burst: tokens_per_claim,
+        };
+
+        let sequence = clock_sequence(&mut rng, 80, 30, tokens_per_claim);
+        assert_eq!(
+            simulate(
+                &RateLimitPolicy::ConfigBucket,
+                cooldown,
+                tokens_per_claim,
+                &sequence
+            ),
+            simulate(&bucket, cooldown, tokens_per_claim, &sequence)
+        );
+    }
+}
+
+#[test]
+fn sliding_window_caps_claims_per_window() {
+    let mut rng = XorShift(0x0123_4567_89ab_cdef);
+
+    for _ in 0..200 {
+        let max_claims = rng.range(1, MAX_WINDOW_CLAIMS as u64) as u8;
+        let window_seconds = rng.range(1, 300) as i64;
+        let policy = RateLimitPolicy::SlidingWindow {
+            max_claims,
+            window_seconds,
+        };
+
+        let sequence = clock_sequence(&mut rng, 100, 20, 1_000);
+        let accepted = simulate(&policy, 0, 1_000, &sequence);
+
+        for &(now, _) in &accepted {
+            let in_window = accepted
+                .iter()
+                .filter(|&&(t, _)| t <= now && now - t < window_seconds)
+                .count();
+            assert!(in_window <= max_claims as usize);
+        }
+    }
+}
+
+#[test]
+fn waiting_the_reported_time_allows_the_claim() {
+    let mut rng = XorShift(0x5555_aaaa_1234_4321);
+
+    let policies = [
+        RateLimitPolicy::FixedCooldown,
+        RateLimitPolicy::TokenBucket {
+            refill_amount: 100,
+            refill_interval: 7,
+            burst: 1_000,
+        },
+        RateLimitPolicy::SlidingWindow {
+            max_claims: 3,
+            window_seconds: 50,
+        },
+    ];
+
+    for policy in &policies {
+        let mut record = UserClaimedRecord::new(Pubkey::new_unique());
+        let mut now = 1_000_000i64;
+
+        for _ in 0..200 {
+            now += rng.range(0, 10) as i64;
+            let amount = rng.range(1, 1_000);
+
+            if let Err(wait) = policy.try_consume(30, 100, &mut record, now, amount) {
+                now += wait;
+                assert!(
+                    policy
+                        .try_consume(30, 100, &mut record, now, amount)
+                        .is_ok()
+                );
+            }
+            record.total_claims += 1;
+        }
+    }
+}
+
+#[test]
+fn invalid_policies_are_rejected() {
+    assert!(
+        RateLimitPolicy::SlidingWindow {
+            max_claims: MAX_WINDOW_CLAIMS as u8 + 1,
+            window_seconds: 60,
+        }
+        .validate()
+        .is_err()
+    );
+    assert!(
+        RateLimitPolicy::TokenBucket {
+            refill_amount: 1,
+            refill_interval: 60,
+            burst: 0,
+        }
+        .validate()
+        .is_err()
+    );
+    assert!(RateLimitPolicy::FixedCooldown.validate().is_ok());
+}
+
+#[test]
+fn returning_a_claim_undoes_it() {
+    let tokens_per_claim = 100;
+    let policies = [
+        RateLimitPolicy::FixedCooldown,
+        RateLimitPolicy::TokenBucket {
+            refill_amount: 100,
+            refill_interval: 60,
+            burst: 100,
+        },
+        RateLimitPolicy::SlidingWindow {
+            max_claims: 1,
+            window_seconds: 60,
+        },
+        RateLimitPolicy::ConfigBucket,
+    ];
+
+    for policy in policies {
+        let mut record = UserClaimedRecord::new(Pubkey::new_unique());
+        policy
+            .try_consume(60, tokens_per_claim, &mut record, 1_000, 100)
+            .unwrap();
+        assert!(
+            policy
+                .try_consume(60, tokens_per_claim, &mut record.clone(), 1_010, 100)
+                .is_err()
+        );
+
+        //half a claim back isn't enough for a full claim yet
+        let mut partial = record.clone();
+        policy.credit_return(60, tokens_per_claim, &mut partial, 1_010, 50);
+        assert!(
+            policy
+                .try_consume(60, tokens_per_claim, &mut partial.clone(), 1_010, 100)
+                .is_err()
+        );
+
+        policy.credit_return(60, tokens_per_claim, &mut record, 1_010, 100);
+        assert!(
+            policy
+                .try_consume(60, tokens_per_claim, &mut record, 1_010, 100)
+                .is_ok(),
+            "{policy:?} kept the returned claim"
+        );
+    }
+}
@@ -0,0 +1,54 @@
+use solana_program::pubkey::Pubkey;
+use token_faucet_backend::{
+    FaucetError, MAX_VESTING_GRANTS, VestingAccount, VestingGrant, VestingSchedule,
+};
+
+const SCHEDULE: VestingSchedule = VestingSchedule {
+    cliff_seconds: 100,
+    duration_seconds: 1_000,
+};
+
+fn grant(amount: u64, start: i64) -> VestingGrant {
+    VestingGrant {
+        amount,
+        start,
+        schedule: SCHEDULE,
+    }
+}
+
+#[test]
+fn later_grants_dont_delay_earlier_ones() {
+    let mut vesting = VestingAccount::new(Pubkey::new_unique());
+    vesting.add_grant(grant(1_000, 0)).unwrap();
+    vesting.add_grant(grant(1_000, 500)).unwrap();
+
+    //before the second grant's cliff only the first one counts
+    assert_eq!(vesting.vested(550), 550);
+    assert_eq!(vesting.vested(1_000), 1_000 + 500);
+    assert_eq!(vesting.vested(1_500), 2_000);
+
+    vesting.released = 1_200;
+    assert_eq!(vesting.releasable(1_000), 300);
+}
+
+#[test]
+fn fully_vested_grants_free_their_slot() {
+    let mut vesting = VestingAccount::new(Pubkey::new_unique());
+    for start in 0..MAX_VESTING_GRANTS as i64 {
+        vesting.add_grant(grant(1_000, start)).unwrap();
+    }
+
+    assert!(matches!(
+        vesting.add_grant(grant(1_000, 500)),
+        Err(FaucetError::TooManyVestingGrants)
+    ));
+
+    //once the oldest grant has fully vested there is room again, and nothing is lost
+    vesting.add_grant(grant(1_000, 1_000)).unwrap();
+    assert_eq!(vesting.grants.len(), MAX_VESTING_GRANTS);
+    assert_eq!(vesting.total, (MAX_VESTING_GRANTS as u64 + 1) * 1_000);
+    assert_eq!(
+        vesting.vested(1_000),
+        1_000 + 999 + 998 + 997 + 996 + 995 + 994 + 993
+    );
+}
@@ -1,6 +1,10 @@
 import React, { useEffect, useState } from 'react';
 import { useWallet, useConnection } from '@solana/wallet-adapter-react';
-import { FaucetService, type FaucetConfig } from '../services/faucet.service';
+import {
+  FaucetService,
+  PAUSE_CLAIMS,
+  type FaucetConfig,
+} from '../services/faucet.service';
 import toast from 'react-hot-toast';
 
 const FaucetStats: React.FC = () => {
@@ -66,12 +70,12 @@ const FaucetStats: React.FC = () => {
           <span className='font-semibold'>Status:</span>{' '}
           <span
             className={
-              faucetConfig.is_active === true
+              (faucetConfig.paused & PAUSE_CLAIMS) === 0
                 ? 'text-green-400'
                 : 'text-red-400'
             }
           >
-            {faucetConfig.is_active === true ? 'Active' : 'Inactive'}
+            {(faucetConfig.paused & PAUSE_CLAIMS) === 0 ? 'Active' : 'Inactive'}
           </span>
         </p>
       </div>
@@ -10,17 +10,25 @@ import type { WalletContextState } from '@solana/wallet-adapter-react';
 import * as borsh from '@coral-xyz/borsh'; //raw blockchain data -> readable js
 import { toast } from 'react-hot-toast';
 import { Buffer } from 'buffer';
-import { TOKEN_PROGRAM_ID, getAssociatedTokenAddress } from '@solana/spl-token';
+import {
+  TOKEN_PROGRAM_ID,
+  createAssociatedTokenAccountIdempotentInstruction,
+  getAssociatedTokenAddress,
+} from '@solana/spl-token';
 
 const PROGRAM_ID = new PublicKey(import.meta.env.VITE_PROGRAM_ID || '');
 
+//matching rust PAUSE_* flags
+export const PAUSE_CLAIMS = 1 << 0;
+
 //matching rust config
 export class FaucetConfig {
   admin!: Uint8Array;
   token_mint!: Uint8Array;
+  treasury!: Uint8Array; // token account every payout comes from
   tokens_per_claim!: bigint;
   cooldown_seconds!: bigint;
-  is_active!: boolean;
+  paused!: number; // PAUSE_* bit flags
 
   constructor(field: FaucetConfig) {
     Object.assign(this, field);
@@ -30,9 +38,10 @@ export class FaucetConfig {
 const faucetConfigSchema = borsh.struct([
   borsh.array(borsh.u8(), 32, 'admin'),
   borsh.array(borsh.u8(), 32, 'token_mint'),
+  borsh.array(borsh.u8(), 32, 'treasury'),
   borsh.u64('tokens_per_claim'),
   borsh.i64('cooldown_seconds'),
-  borsh.bool('is_active'),
+  borsh.u8('paused'),
 ]);
 
 export class FaucetService {
@@ -80,6 +89,20 @@ export class FaucetService {
 
     const [faucetConfigPDA] = this.getFaucetConfigPDA();
 
+    //treasury is the config PDA's associated token account, pinned by the program at init
+    const treasury = await getAssociatedTokenAddress(
+      tokenMint,
+      faucetConfigPDA,
+      true
+    );
+    const createTreasuryInstruction =
+      createAssociatedTokenAccountIdempotentInstruction(
+        this.wallet.publicKey,
+        treasury,
+        faucetConfigPDA,
+        tokenMint
+      );
+
     //instruction data
     const instructionData = Buffer.alloc(1 + 8 + 8);
 
@@ -114,6 +137,9 @@ export class FaucetService {
 
         // Account 3: System program (for PDA creation)
         { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+
+        // Account 4: Faucet treasury token account
+        { pubkey: treasury, isSigner: false, isWritable: false },
       ],
       programId: PROGRAM_ID,
       data: instructionData,
@@ -122,7 +148,10 @@ export class FaucetService {
     console.log('Instruction Created!');
 
     //creating and sending transaction
-    const transaction = new Transaction().add(initTransaction);
+    const transaction = new Transaction().add(
+      createTreasuryInstruction,
+      initTransaction
+    );
 
     // get recent blockhash before signing
     console.log('Getting recent blockhash...');
@@ -172,7 +201,7 @@ export class FaucetService {
       console.log('✅ Faucet config loaded:', {
         tokensPerClaim: config.tokens_per_claim.toString(),
         cooldownSeconds: config.cooldown_seconds.toString(),
-        isActive: config.is_active,
+        paused: config.paused,
       });
 
       return config;
@@ -204,7 +233,7 @@ export class FaucetService {
 
     const [faucetConfigPDA] = this.getFaucetConfigPDA();
     const [userClaimPDA] = this.getUserClaimPDA(this.wallet.publicKey);
-    const [faucetTreasuryPDA] = this.getFaucetTreasuryPDA(tokenMint);
+    const faucetTreasury = new PublicKey(faucetConfig.treasury);
 
     const userTokenAccount = await getAssociatedTokenAddress(
       tokenMint,
@@ -212,8 +241,10 @@ export class FaucetService {
     );
 
     //instruction data for claiming tokens
-    const instructionData = Buffer.alloc(1); //creates 1 byte telling Rust program "this is a ClaimTokens request"
+    const instructionData = Buffer.alloc(3); //first byte tells Rust program "this is a ClaimTokens request"
     instructionData.writeUInt8(1, 0); // 1 for claim operation (2nd instruction in enum)
+    instructionData.writeUInt8(0, 1); // amount: None -> claim the maximum allowed
+    instructionData.writeUInt8(0, 2); // referrer: None -> no referral accounts follow
 
     console.log("PDA's calculated:", {
       faucetConfigPDA: faucetConfigPDA.toString(),
@@ -226,8 +257,8 @@ export class FaucetService {
         { pubkey: this.wallet.publicKey, isSigner: true, isWritable: true }, // user (signer)
         { pubkey: userClaimPDA, isSigner: false, isWritable: true }, // user claim record
         { pubkey: userTokenAccount, isSigner: false, isWritable: true }, // user token account
-        { pubkey: faucetTreasuryPDA, isSigner: false, isWritable: true }, // faucet treasury
-        { pubkey: faucetConfigPDA, isSigner: false, isWritable: false }, // faucet config
+        { pubkey: faucetTreasury, isSigner: false, isWritable: true }, // faucet treasury
+        { pubkey: faucetConfigPDA, isSigner: false, isWritable: true }, // faucet config
         { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false }, // token program
         { pubkey: SystemProgram.programId, isSigner: false, isWritable: false }, // system program
         { pubkey: faucetConfigPDA, isSigner: false, isWritable: false }, // faucet authority (SAME as config PDA!)
@@ -264,11 +295,4 @@ export class FaucetService {
       PROGRAM_ID
     );
   }
-
-  getFaucetTreasuryPDA(tokenMint: PublicKey): [PublicKey, number] {
-    return PublicKey.findProgramAddressSync(
-      [Buffer.from('faucet_treasury'), tokenMint.toBuffer()],
-      PROGRAM_ID
-    );
-  }
 }